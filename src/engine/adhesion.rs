@@ -0,0 +1,138 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cgmath::InnerSpace;
+
+use crate::shared::cell::{near, CellInformation};
+
+/// Links between cells that have been touching for a while.
+/// Like the middle lamella they hold the cells together when growth or pushing would separate them.
+pub struct Adhesion {
+    /// number of updates in a row the two cells have been touching
+    contacts: HashMap<(u64, u64), u32>,
+    /// ordered, so the links pull one after the other in the same order in every run
+    links: BTreeSet<(u64, u64)>,
+}
+
+impl Adhesion {
+    pub fn new() -> Self {
+        Self {
+            contacts: HashMap::new(),
+            links: BTreeSet::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.contacts.clear();
+        self.links.clear();
+    }
+
+    /// counts for how many updates each pair of cells has been touching
+    /// and links the pairs that have been touching for the given number of updates
+    pub fn update_contacts(
+        &mut self,
+        cells: &[CellInformation<f32>],
+        near_cells: &[HashMap<u64, CellInformation<f32>>],
+        updates_needed: u32,
+    ) {
+        let mut contacts = HashMap::new();
+        for (cell, near_cells) in cells.iter().zip(near_cells) {
+            for other in near_cells.values() {
+                if !near(&cell.position, cell.radius, &other.position, other.radius) {
+                    continue;
+                }
+                let pair = pair(cell.id, other.id);
+                let count = self.contacts.get(&pair).copied().unwrap_or(0) + 1;
+                contacts.insert(pair, count);
+                if count >= updates_needed {
+                    self.links.insert(pair);
+                }
+            }
+        }
+        self.contacts = contacts;
+        let ids: HashSet<u64> = cells.iter().map(|cell| cell.id).collect();
+        self.links
+            .retain(|(id1, id2)| ids.contains(id1) && ids.contains(id2));
+    }
+
+    /// pulls linked cells that are further apart than touching towards each other,
+    /// the stiffness is the fraction of the gap closed per update
+    pub fn pull(&self, cells: &mut [CellInformation<f32>], stiffness: f32) {
+        if stiffness <= 0. {
+            return;
+        }
+        let index_by_id: HashMap<u64, usize> = cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (cell.id, index))
+            .collect();
+        for (id1, id2) in self.links.iter() {
+            let (Some(&i), Some(&j)) = (index_by_id.get(id1), index_by_id.get(id2)) else {
+                continue;
+            };
+            let offset = cells[j].position - cells[i].position;
+            let distance = offset.magnitude();
+            let rest_length = cells[i].radius + cells[j].radius;
+            if distance <= rest_length {
+                continue;
+            }
+            let weight_i = cells[i].radius.powi(3);
+            let weight_j = cells[j].radius.powi(3);
+            let share_i = weight_j / (weight_i + weight_j);
+            let correction = offset / distance * (distance - rest_length) * stiffness.min(1.);
+            cells[i].position += correction * share_i;
+            cells[j].position -= correction * (1. - share_i);
+        }
+    }
+}
+
+/// the ids of two cells in the same order no matter which cell comes first
+fn pair(id1: u64, id2: u64) -> (u64, u64) {
+    (id1.min(id2), id1.max(id2))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+
+    use super::*;
+
+    fn cells() -> Vec<CellInformation<f32>> {
+        (0..6)
+            .map(|id| CellInformation {
+                id,
+                position: Point3::new(id as f32 * 2.5, (id % 2) as f32, 0.),
+                radius: 1. + id as f32 * 0.1,
+            })
+            .collect()
+    }
+
+    /// adhesion with every neighbouring pair of the cells linked, added in the given order
+    fn linked(order: impl Iterator<Item = u64>) -> Adhesion {
+        let mut adhesion = Adhesion::new();
+        for id in order {
+            adhesion.links.insert(pair(id, id + 1));
+        }
+        adhesion
+    }
+
+    #[test]
+    fn pull_does_not_depend_on_the_order_links_were_made() {
+        let mut forward = cells();
+        let mut backward = cells();
+        linked(0..5).pull(&mut forward, 0.5);
+        linked((0..5).rev()).pull(&mut backward, 0.5);
+        for (a, b) in forward.iter().zip(backward.iter()) {
+            assert_eq!(a.position, b.position);
+        }
+    }
+
+    #[test]
+    fn pull_closes_the_gap_between_linked_cells() {
+        let mut cells = cells();
+        let before = (cells[1].position - cells[0].position).magnitude();
+        linked(0..1).pull(&mut cells, 0.5);
+        let after = (cells[1].position - cells[0].position).magnitude();
+        assert!(after < before);
+        assert!(after >= cells[0].radius + cells[1].radius);
+    }
+}
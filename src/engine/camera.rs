@@ -12,6 +12,7 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.0, 1.0,
 );
 
+#[derive(Clone, Copy)]
 pub struct Camera {
     pub eye: cgmath::Point3<f32>,
     pub target: cgmath::Point3<f32>,
@@ -20,6 +21,19 @@ pub struct Camera {
     pub fovy: f32,
     pub znear: f32,
     pub zfar: f32,
+    /// without perspective, sizes do not depend on the distance to the camera
+    pub orthographic: bool,
+}
+
+/// Directions the camera can look at its target from.
+#[derive(Clone, Copy, Debug)]
+pub enum StandardView {
+    /// from above, looking down the y axis
+    Top,
+    /// from the front, looking down the z axis
+    Front,
+    /// from the right, looking down the x axis
+    Side,
 }
 
 impl Camera {
@@ -28,13 +42,40 @@ impl Camera {
         // It's essentially an inverse of whatever the transform matrix of the camera would be.
         let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
         // The proj matrix warps the scene to give the effect of depth. Without this, objects up close would be the same size as objects far away.
-        let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
+        let proj = if self.orthographic {
+            // show as much around the target as the perspective projection would
+            let height = self.view_height();
+            let width = height * self.aspect;
+            cgmath::ortho(-width, width, -height, height, self.znear, self.zfar)
+        } else {
+            cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar)
+        };
 
         // The coordinate system in Wgpu is based on DirectX and Metal's coordinate systems.
         // That means that in normalized device coordinates (opens new window), the x-axis and y-axis are in the range of -1.0 to +1.0, and the z-axis is 0.0 to +1.0.
         // The cgmath crate (as well as most game math crates) is built for OpenGL's coordinate system.
         return OPENGL_TO_WGPU_MATRIX * proj * view;
     }
+
+    /// half the height of the part of the scene that is shown at the distance of the target,
+    /// the same for both projections
+    pub fn view_height(&self) -> f32 {
+        use cgmath::{Angle, InnerSpace};
+        (self.target - self.eye).magnitude() * cgmath::Deg(self.fovy / 2.).tan()
+    }
+
+    /// moves the eye so that it looks at the target from the direction of the view, keeping the distance
+    pub fn look_from(&mut self, view: StandardView) {
+        use cgmath::{InnerSpace, Vector3};
+        let distance = (self.target - self.eye).magnitude();
+        let (direction, up) = match view {
+            StandardView::Top => (Vector3::unit_y(), -Vector3::unit_z()),
+            StandardView::Front => (Vector3::unit_z(), Vector3::unit_y()),
+            StandardView::Side => (Vector3::unit_x(), Vector3::unit_y()),
+        };
+        self.eye = self.target + direction * distance;
+        self.up = up;
+    }
 }
 
 // We need this for Rust to store our data correctly for the shaders
@@ -105,7 +146,7 @@ impl CameraController {
             ..
         } = event
         else {
-            panic!("Cannot get keycode from KeyEvent!");
+            return false;
         };
         let is_pressed = event.state == ElementState::Pressed;
         match keycode {
@@ -0,0 +1,69 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::shared::{cell::Cell, math::radius_from_volume};
+
+/// Directory screenshots, turntable recordings and point clouds are written to.
+pub const CAPTURE_DIRECTORY: &str = "screenshots";
+/// Number of images of a turntable recording, one full orbit of the camera.
+pub const TURNTABLE_FRAMES: u32 = 72;
+
+/// returns a path in the directory that contains the current time
+pub fn timestamped_path(directory: &str, prefix: &str, extension: &str) -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    Path::new(directory).join(format!("{}_{}{}", prefix, seconds, extension))
+}
+
+/// writes 8 bit rgba pixels row by row as png image
+pub fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), Box<dyn Error>> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(())
+}
+
+/// writes the center of every cell with its attributes as ascii PLY point cloud
+pub fn write_point_cloud(path: &Path, cells: &[Cell]) -> Result<(), Box<dyn Error>> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    writeln!(writer, "element vertex {}", cells.len())?;
+    for property in ["x", "y", "z", "radius", "volume", "carbon", "time_lived"] {
+        writeln!(writer, "property float {}", property)?;
+    }
+    writeln!(writer, "end_header")?;
+    for cell in cells {
+        let bio = cell.bio.read().unwrap();
+        let position = bio.position_clone();
+        let volume = *bio.volume();
+        writeln!(
+            writer,
+            "{} {} {} {} {} {} {}",
+            position.x,
+            position.y,
+            position.z,
+            radius_from_volume(&volume),
+            volume,
+            bio.carbon(),
+            bio.time_lived()
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
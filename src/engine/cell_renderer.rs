@@ -1,7 +1,13 @@
 use super::vertex::Vertex;
-use crate::shared::{
-    cell::{CellEventType, CellInformation, EventSystem},
-    math::{self, distance, point_vs_plane, signed_distance, Plane, Point2PlaneClassification},
+use crate::{
+    model::cell::MaturationStage,
+    shared::{
+        cell::{CellEventType, CellInformation, EventSystem},
+        math::{
+            self, distance, point_vs_plane, radius_from_volume, signed_distance, Plane,
+            Point2PlaneClassification,
+        },
+    },
 };
 use cgmath::{InnerSpace, Point3, Vector3};
 use std::{
@@ -10,6 +16,56 @@ use std::{
     sync::{Arc, RwLock, RwLockReadGuard},
 };
 
+/// How the planes that cut a cell against its neighbours are placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeometryMode {
+    /// cut overlapping cells in the middle of their overlap
+    Overlap,
+    /// cut every neighbour at the radical plane, the cells become the cells of a power diagram
+    PowerDiagram,
+}
+
+impl GeometryMode {
+    pub fn next(self) -> Self {
+        match self {
+            GeometryMode::Overlap => GeometryMode::PowerDiagram,
+            GeometryMode::PowerDiagram => GeometryMode::Overlap,
+        }
+    }
+}
+
+/// Which property of the cells is shown by their color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    None,
+    Age,
+    Stage,
+    Volume,
+    Carbon,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::None => ColorMode::Age,
+            ColorMode::Age => ColorMode::Stage,
+            ColorMode::Stage => ColorMode::Volume,
+            ColorMode::Volume => ColorMode::Carbon,
+            ColorMode::Carbon => ColorMode::None,
+        }
+    }
+}
+
+/// color of cells if no property is shown
+pub const CELL_COLOR: [f32; 3] = [1., 1., 1.];
+const MARK_COLOR: [f32; 3] = [1., 1., 0.];
+const YOUNG_COLOR: [f32; 3] = [0.6, 1., 0.4];
+const EXPANDING_COLOR: [f32; 3] = [0.1, 0.8, 0.1];
+const MATURE_COLOR: [f32; 3] = [0., 0.4, 0.1];
+const SENESCENT_COLOR: [f32; 3] = [0.6, 0.4, 0.1];
+/// how much marked cells are tinted towards the mark color
+const MARK_TINT: f32 = 0.7;
+
 #[derive(Clone, Debug)]
 pub struct CellRenderer {
     marked: Arc<RwLock<bool>>,
@@ -95,7 +151,13 @@ impl CellRenderer {
         });
     }
 
-    pub fn update(&mut self, lod: u16, near_cells: &HashMap<u64, CellInformation<f32>>) {
+    pub fn update(
+        &mut self,
+        lod: u16,
+        geometry_mode: GeometryMode,
+        color: [f32; 3],
+        near_cells: &HashMap<u64, CellInformation<f32>>,
+    ) {
         self.vertices = Vec::new();
         self.indices = Vec::new();
 
@@ -107,13 +169,21 @@ impl CellRenderer {
 
         let mut planes = vec![];
         near_cells.values().for_each(|c| {
-            let plane = self.try_create_intersection_plane(c);
+            let plane = match geometry_mode {
+                GeometryMode::Overlap => self.try_create_intersection_plane(c),
+                GeometryMode::PowerDiagram => self.try_create_radical_plane(c),
+            };
             if plane.is_some() {
                 planes.push(plane.unwrap());
             }
         });
         let pos = self.position_clone();
         let radius = self.radius_clone();
+        let color = if *self.marked.read().unwrap() {
+            mix(color, MARK_COLOR, MARK_TINT)
+        } else {
+            color
+        };
 
         for i in 0..=stack_count {
             let stack_angle = PI / 2.0 - i as f32 * stack_step;
@@ -125,10 +195,6 @@ impl CellRenderer {
                 let x = xy * sector_angle.cos();
                 let y = xy * sector_angle.sin();
 
-                let mut color = [1., 1., 1.];
-                if *self.marked.read().unwrap() {
-                    color = [1., 1., 0.];
-                }
                 let vertex = Vertex {
                     position: [x + pos.x, y + pos.y, z + pos.z],
                     color,
@@ -202,6 +268,33 @@ impl CellRenderer {
         }
     }
 
+    /// Returns None if other is the same as self or both cells have the same center
+    /// Returns Some(plane) otherwise where plane is the radical plane of both cells,
+    /// all points on it have the same power distance to both cells
+    fn try_create_radical_plane(&self, other: &CellInformation<f32>) -> Option<Plane<f32>> {
+        if other.id == self.cell_id {
+            return None;
+        }
+        let p1 = self.position_clone();
+        let r1 = self.radius_clone();
+        let v_cell_to_cell = other.position - p1;
+        let dist = v_cell_to_cell.magnitude();
+        if dist <= f32::EPSILON {
+            return None;
+        }
+        let normal = v_cell_to_cell / dist;
+        // distance of the plane from this cells center
+        let offset = (dist * dist + r1 * r1 - other.radius * other.radius) / (2. * dist);
+        Some(Plane::<f32> {
+            pos: Vector3 {
+                x: p1.x,
+                y: p1.y,
+                z: p1.z,
+            } + normal * offset,
+            normal,
+        })
+    }
+
     /// look at the planes that divide this cell from near neighbours
     /// if the vertex is on the same side as the center, keep it
     /// else move it to the plane
@@ -234,15 +327,35 @@ impl CellRenderer {
     }
 }
 
-pub fn radius_from_volume(volume: &f32) -> f32 {
-    // r = ((3V)/(4PI))^(1/3)
-    f32::powf((3. * volume) / (4. * PI), 1. / 3.)
+/// light green for young cells, darker for older ones and brown for senescent cells
+pub fn stage_color(stage: MaturationStage) -> [f32; 3] {
+    match stage {
+        MaturationStage::Young => YOUNG_COLOR,
+        MaturationStage::Expanding => EXPANDING_COLOR,
+        MaturationStage::Mature => MATURE_COLOR,
+        MaturationStage::Senescent => SENESCENT_COLOR,
+    }
+}
+
+/// maps a value between 0 and 1 to a color from blue over green to red
+pub fn color_map(value: f32) -> [f32; 3] {
+    let value = value.clamp(0., 1.);
+    if value < 0.5 {
+        mix([0., 0., 1.], [0., 1., 0.], value * 2.)
+    } else {
+        mix([0., 1., 0.], [1., 0., 0.], value * 2. - 1.)
+    }
+}
+
+/// linear interpolation between two colors, factor 0 returns from and 1 returns to
+fn mix(from: [f32; 3], to: [f32; 3], factor: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * factor)
 }
 
 /// expects two overlapping cells
 /// calculates the overlap in a straight line between the cells centers
 /// returns the point in the middle of the overlap
-fn between_depending_on_radius(
+pub fn between_depending_on_radius(
     cell1: &CellInformation<f32>,
     cell2: &CellInformation<f32>,
 ) -> Point3<f32> {
@@ -0,0 +1,94 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{model::entity::Entity, shared::cell::Cell};
+
+/// Writes the state of the simulation after every update as csv,
+/// one row per update with values summed up over all cells and optionally one row per cell.
+pub struct DataLogger {
+    aggregates: Option<BufWriter<File>>,
+    cells: Option<BufWriter<File>>,
+}
+
+impl DataLogger {
+    /// no file is written for paths that are None
+    pub fn new(aggregates_path: Option<&Path>, cells_path: Option<&Path>) -> io::Result<Self> {
+        let aggregates = match aggregates_path {
+            Some(path) => Some(create_csv(
+                path,
+                "tick,cell_count,total_volume,mean_volume,max_volume,total_carbon,light_intensity",
+            )?),
+            None => None,
+        };
+        let cells = match cells_path {
+            Some(path) => Some(create_csv(
+                path,
+                "tick,cell_id,x,y,z,volume,carbon,time_lived,age",
+            )?),
+            None => None,
+        };
+        Ok(Self { aggregates, cells })
+    }
+
+    pub fn log(&mut self, tick: u64, cells: &[Cell], light_intensity: f32) -> io::Result<()> {
+        if let Some(writer) = self.aggregates.as_mut() {
+            let mut total_volume = 0.;
+            let mut max_volume: f32 = 0.;
+            let mut total_carbon = 0.;
+            for cell in cells {
+                let bio = cell.bio.read().unwrap();
+                let volume = *bio.volume();
+                total_volume += volume;
+                max_volume = max_volume.max(volume);
+                total_carbon += bio.carbon();
+            }
+            let mean_volume = if cells.is_empty() {
+                0.
+            } else {
+                total_volume / cells.len() as f32
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                tick,
+                cells.len(),
+                total_volume,
+                mean_volume,
+                max_volume,
+                total_carbon,
+                light_intensity
+            )?;
+            writer.flush()?;
+        }
+        if let Some(writer) = self.cells.as_mut() {
+            for cell in cells {
+                let bio = cell.bio.read().unwrap();
+                let position = bio.position_clone();
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{},{}",
+                    tick,
+                    bio.entity_id(),
+                    position.x,
+                    position.y,
+                    position.z,
+                    *bio.volume(),
+                    bio.carbon(),
+                    bio.time_lived(),
+                    bio.age()
+                )?;
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn create_csv(path: &Path, header: &str) -> io::Result<BufWriter<File>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", header)?;
+    Ok(writer)
+}
@@ -0,0 +1,316 @@
+use std::{collections::HashSet, f32::consts::PI};
+
+use cgmath::{InnerSpace, Point3, Vector3};
+
+use crate::{
+    model::{cell::CellSnapshot, obstacle::Obstacle},
+    shared::cell::CellInformation,
+};
+
+use super::{
+    camera::Camera,
+    cell_renderer::between_depending_on_radius,
+    delaunay::{get_near_cells, TetGenResult},
+    vertex::Vertex,
+};
+
+const TETRAEDER_COLOR: [f32; 3] = [0., 1., 1.];
+const PLANE_COLOR: [f32; 3] = [1., 0., 1.];
+const OBSTACLE_COLOR: [f32; 3] = [0.6, 0.6, 0.6];
+const MEASURE_COLOR: [f32; 3] = [1., 0.5, 0.];
+const GRID_COLOR: [f32; 3] = [0.3, 0.3, 0.3];
+const TIMELINE_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+const VOLUME_COLOR: [f32; 3] = [0., 1., 0.];
+const CARBON_COLOR: [f32; 3] = [1., 1., 0.];
+const SCALE_BAR_COLOR: [f32; 3] = [1., 1., 1.];
+/// distance of the scale bar to the left and the bottom edge of the view, relative to half its size
+const SCALE_BAR_MARGIN: f32 = 0.1;
+/// size of the plot of the history of a cell
+const TIMELINE_WIDTH: f32 = 4.;
+const TIMELINE_HEIGHT: f32 = 2.;
+/// half the side length of the square drawn for a plane obstacle
+const OBSTACLE_PLANE_SIZE: f32 = 3.;
+/// number of line segments of a circle drawn for a sphere obstacle
+const OBSTACLE_CIRCLE_SEGMENTS: usize = 32;
+
+/// returns pairs of vertices, each pair is one edge of a tetraeder of the delaunay triangulation
+pub fn tetraeder_edges(tet_gen_result: &TetGenResult<f32>) -> Vec<Vertex> {
+    let mut lines = vec![];
+    if let TetGenResult::Success { tetraeders, .. } = tet_gen_result {
+        for tetraeder in tetraeders {
+            let points = tetraeder.points();
+            for i in 0..4 {
+                for j in (i + 1)..4 {
+                    lines.push(vertex(points[i], TETRAEDER_COLOR));
+                    lines.push(vertex(points[j], TETRAEDER_COLOR));
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// returns pairs of vertices that outline a square on each plane that separates two overlapping cells
+pub fn overlap_planes(
+    cells: &[CellInformation<f32>],
+    tet_gen_result: &TetGenResult<f32>,
+) -> Vec<Vertex> {
+    let mut lines = vec![];
+    let mut drawn = HashSet::new();
+    for cell in cells {
+        for other in get_near_cells(cell, tet_gen_result).values() {
+            let pair = (u64::min(cell.id, other.id), u64::max(cell.id, other.id));
+            if !drawn.insert(pair) {
+                continue;
+            }
+            let center = between_depending_on_radius(cell, other);
+            let normal = (other.position - cell.position).normalize();
+            let size = f32::min(cell.radius, other.radius);
+            lines.append(&mut square(center, normal, size, PLANE_COLOR));
+        }
+    }
+    lines
+}
+
+/// returns pairs of vertices that outline a square on each plane and three circles around each sphere
+pub fn obstacles(obstacles: &[Obstacle]) -> Vec<Vertex> {
+    let mut lines = vec![];
+    for obstacle in obstacles {
+        match *obstacle {
+            Obstacle::Plane { point, normal } => {
+                let normal = Vector3::from(normal).normalize();
+                lines.append(&mut square(
+                    point.into(),
+                    normal,
+                    OBSTACLE_PLANE_SIZE,
+                    OBSTACLE_COLOR,
+                ));
+            }
+            Obstacle::Sphere { center, radius } => {
+                let center = Point3::from(center);
+                for (u, v) in [
+                    (Vector3::unit_x(), Vector3::unit_y()),
+                    (Vector3::unit_y(), Vector3::unit_z()),
+                    (Vector3::unit_z(), Vector3::unit_x()),
+                ] {
+                    let point = |i: usize| {
+                        let angle = 2. * PI * i as f32 / OBSTACLE_CIRCLE_SEGMENTS as f32;
+                        center + (u * angle.cos() + v * angle.sin()) * radius
+                    };
+                    for i in 0..OBSTACLE_CIRCLE_SEGMENTS {
+                        lines.push(vertex(&point(i), OBSTACLE_COLOR));
+                        lines.push(vertex(&point(i + 1), OBSTACLE_COLOR));
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// returns pairs of vertices that connect the points one after the other
+pub fn measurement(points: &[Point3<f32>]) -> Vec<Vertex> {
+    let mut lines = vec![];
+    for segment in points.windows(2) {
+        lines.push(vertex(&segment[0], MEASURE_COLOR));
+        lines.push(vertex(&segment[1], MEASURE_COLOR));
+    }
+    lines
+}
+
+/// returns pairs of vertices of a square grid on the ground plane around the origin
+/// and of the x, y and z axes in red, green and blue, each as long as one grid spacing
+pub fn grid(spacing: f32, lines_per_side: u32) -> Vec<Vertex> {
+    let mut lines = vec![];
+    let extent = spacing * lines_per_side as f32;
+    let lines_per_side = lines_per_side as i32;
+    for i in -lines_per_side..=lines_per_side {
+        let offset = spacing * i as f32;
+        for (start, end) in [
+            (
+                Point3::new(offset, 0., -extent),
+                Point3::new(offset, 0., extent),
+            ),
+            (
+                Point3::new(-extent, 0., offset),
+                Point3::new(extent, 0., offset),
+            ),
+        ] {
+            lines.push(vertex(&start, GRID_COLOR));
+            lines.push(vertex(&end, GRID_COLOR));
+        }
+    }
+    for (axis, color) in [
+        (Vector3::unit_x(), [1., 0., 0.]),
+        (Vector3::unit_y(), [0., 1., 0.]),
+        (Vector3::unit_z(), [0., 0., 1.]),
+    ] {
+        let origin = Point3::new(0., 0., 0.);
+        lines.push(vertex(&origin, color));
+        lines.push(vertex(&(origin + axis * spacing), color));
+    }
+    lines
+}
+
+/// returns pairs of vertices of a plot of the volume in green and the sugar in yellow over the ticks of the history,
+/// each scaled to its highest value, with a vertical line at the given tick.
+/// The plot stands upright with its lower left corner at the origin.
+pub fn timeline(origin: Point3<f32>, history: &[(u64, CellSnapshot)], tick: u64) -> Vec<Vertex> {
+    let (Some((first, _)), Some((last, _))) = (history.first(), history.last()) else {
+        return vec![];
+    };
+    let span = (last - first).max(1) as f32;
+    let x = |tick: u64| TIMELINE_WIDTH * tick.saturating_sub(*first) as f32 / span;
+    let point = |x: f32, y: f32| origin + Vector3::new(x, y, 0.);
+    let mut lines = vec![];
+    for (start, end) in [
+        ((0., 0.), (TIMELINE_WIDTH, 0.)),
+        ((0., 0.), (0., TIMELINE_HEIGHT)),
+        ((x(tick), 0.), (x(tick), TIMELINE_HEIGHT)),
+    ] {
+        lines.push(vertex(&point(start.0, start.1), TIMELINE_COLOR));
+        lines.push(vertex(&point(end.0, end.1), TIMELINE_COLOR));
+    }
+    let volumes: Vec<f32> = history.iter().map(|(_, cell)| cell.volume).collect();
+    let carbon: Vec<f32> = history.iter().map(|(_, cell)| cell.carbon).collect();
+    for (values, color) in [(volumes, VOLUME_COLOR), (carbon, CARBON_COLOR)] {
+        let max = values.iter().fold(0f32, |max, value| max.max(*value));
+        let scale = if max > 0. { TIMELINE_HEIGHT / max } else { 0. };
+        let points: Vec<Point3<f32>> = history
+            .iter()
+            .zip(values)
+            .map(|((tick, _), value)| point(x(*tick), value.max(0.) * scale))
+            .collect();
+        for segment in points.windows(2) {
+            lines.push(vertex(&segment[0], color));
+            lines.push(vertex(&segment[1], color));
+        }
+    }
+    lines
+}
+
+/// returns the length of the scale bar of the camera in world units, 1, 2 or 5 times a power of ten
+/// so that it covers about a fifth of the width of the view at the distance of the target,
+/// and that power of ten
+pub fn scale_bar_length(camera: &Camera) -> Option<(f32, i32)> {
+    let view_width = 2. * camera.view_height() * camera.aspect;
+    if !view_width.is_finite() || view_width <= 0. {
+        return None;
+    }
+    let exponent = (view_width / 5.).log10().floor() as i32;
+    let power = 10f32.powi(exponent);
+    let factor = match view_width / 5. / power {
+        factor if factor < 2. => 1.,
+        factor if factor < 5. => 2.,
+        _ => 5.,
+    };
+    Some((factor * power, exponent))
+}
+
+/// returns pairs of vertices of a bar with short ends in the lower left corner of the view of the camera,
+/// at the distance of the target, so it has its length wherever the camera looks from
+pub fn scale_bar(camera: &Camera) -> Vec<Vertex> {
+    let Some((length, _)) = scale_bar_length(camera) else {
+        return vec![];
+    };
+    let forward = (camera.target - camera.eye).normalize();
+    let right = forward.cross(camera.up).normalize();
+    let up = right.cross(forward);
+    let half_height = camera.view_height();
+    let half_width = half_height * camera.aspect;
+    let start = camera.target
+        - right * half_width * (1. - SCALE_BAR_MARGIN)
+        - up * half_height * (1. - SCALE_BAR_MARGIN);
+    let end = start + right * length;
+    let tick = up * half_height * SCALE_BAR_MARGIN / 4.;
+    let mut lines = vec![];
+    for (from, to) in [
+        (start, end),
+        (start - tick, start + tick),
+        (end - tick, end + tick),
+    ] {
+        lines.push(vertex(&from, SCALE_BAR_COLOR));
+        lines.push(vertex(&to, SCALE_BAR_COLOR));
+    }
+    lines
+}
+
+/// outline of a square around the center perpendicular to the normal
+fn square(center: Point3<f32>, normal: Vector3<f32>, size: f32, color: [f32; 3]) -> Vec<Vertex> {
+    let helper = if normal.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    let u = normal.cross(helper).normalize() * size;
+    let v = normal.cross(u);
+    let corners = [
+        center + u + v,
+        center - u + v,
+        center - u - v,
+        center + u - v,
+    ];
+    let mut lines = vec![];
+    for i in 0..4 {
+        lines.push(vertex(&corners[i], color));
+        lines.push(vertex(&corners[(i + 1) % 4], color));
+    }
+    lines
+}
+
+fn vertex(point: &Point3<f32>, color: [f32; 3]) -> Vertex {
+    Vertex {
+        position: [point.x, point.y, point.z],
+        color,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(distance: f32) -> Camera {
+        Camera {
+            eye: Point3::new(0., 0., distance),
+            target: Point3::new(0., 0., 0.),
+            up: Vector3::unit_y(),
+            aspect: 1.5,
+            fovy: 45.,
+            znear: 0.1,
+            zfar: 100.,
+            orthographic: false,
+        }
+    }
+
+    #[test]
+    fn the_scale_bar_is_a_round_length() {
+        for distance in [0.3, 3., 7., 40.] {
+            let (length, exponent) = scale_bar_length(&camera(distance)).unwrap();
+            let factor = length / 10f32.powi(exponent);
+            assert!([1., 2., 5.]
+                .iter()
+                .any(|round| (factor - round).abs() < 1e-4));
+            let view_width = 2. * camera(distance).view_height() * 1.5;
+            assert!(length <= view_width / 5. && length > view_width / 12.5);
+        }
+    }
+
+    #[test]
+    fn the_scale_bar_grows_with_the_distance() {
+        let (near, _) = scale_bar_length(&camera(3.)).unwrap();
+        let (far, _) = scale_bar_length(&camera(30.)).unwrap();
+        assert!((far / near - 10.).abs() < 1e-3);
+        assert!(scale_bar_length(&camera(0.)).is_none());
+    }
+
+    #[test]
+    fn the_scale_bar_has_its_length() {
+        let camera = camera(3.);
+        let (length, _) = scale_bar_length(&camera).unwrap();
+        let lines = scale_bar(&camera);
+        let start = Point3::from(lines[0].position);
+        let end = Point3::from(lines[1].position);
+        assert!(((end - start).magnitude() - length).abs() < 1e-5);
+        assert_eq!(start.z, 0.);
+    }
+}
@@ -1,12 +1,15 @@
 use crate::{
     model::entity::Entity,
-    shared::cell::{near, Cell, CellInformation},
+    shared::{
+        cell::{near, Cell, CellInformation},
+        math::radius_from_volume,
+    },
 };
 use cgmath::{BaseFloat, Point3};
 use std::{collections::HashMap, fmt::Debug};
 use tritet::{StrError, Tetgen};
 
-use super::cell_renderer::radius_from_volume;
+use super::spatial_hash::SpatialHash;
 
 #[derive(Clone, Debug)]
 pub struct TetraederOfCells<T: BaseFloat> {
@@ -14,8 +17,12 @@ pub struct TetraederOfCells<T: BaseFloat> {
 }
 
 pub enum TetGenResult<T: BaseFloat> {
-    Success(Vec<TetraederOfCells<T>>),
-    NoTetGenPossible(Vec<CellInformation<T>>),
+    Success {
+        tetraeders: Vec<TetraederOfCells<T>>,
+        /// the cells that share a tetraeder with the cell of the id
+        neighbours: HashMap<u64, Vec<CellInformation<T>>>,
+    },
+    NoTetGenPossible(SpatialHash<T>),
 }
 
 impl<T: BaseFloat> TetraederOfCells<T> {
@@ -47,24 +54,49 @@ impl<T: BaseFloat> TetraederOfCells<T> {
     }
 }
 
+/// collects the neighbours of every cell once, so they do not have to be searched in all tetraeders for each cell
+pub fn with_triangulation<T: BaseFloat>(tetraeders: Vec<TetraederOfCells<T>>) -> TetGenResult<T> {
+    let mut neighbours = HashMap::<u64, Vec<CellInformation<T>>>::new();
+    for tetraeder in tetraeders.iter() {
+        for node in tetraeder.nodes() {
+            let others = neighbours.entry(node.id).or_default();
+            for other in tetraeder.nodes() {
+                if other.id != node.id && !others.iter().any(|known| known.id == other.id) {
+                    others.push(other.clone());
+                }
+            }
+        }
+    }
+    TetGenResult::Success {
+        tetraeders,
+        neighbours,
+    }
+}
+
+/// sorts the cells into a spatial hash instead of triangulating them,
+/// near cells are then found by their distance alone
+pub fn without_triangulation(cells: &[Cell]) -> TetGenResult<f32> {
+    let information: Vec<CellInformation<f32>> = cells
+        .iter()
+        .map(|c| {
+            let renderer = c.renderer.read().unwrap();
+            let position = renderer.position().clone();
+            CellInformation::<f32> {
+                id: renderer.cell_id(),
+                position,
+                radius: renderer.radius_clone(),
+            }
+        })
+        .collect();
+    TetGenResult::NoTetGenPossible(SpatialHash::new(information))
+}
+
 /// uses delaunay triangulation to triangulate the cells centers
 /// returns the resulting tetraeders
 pub fn delaunay_triangulation(cells: &Vec<Cell>) -> Result<TetGenResult<f32>, StrError> {
     let n_points = cells.len();
     if n_points < 4 {
-        let information: Vec<CellInformation<f32>> = cells
-            .iter()
-            .map(|c| {
-                let renderer = c.renderer.read().unwrap();
-                let position = renderer.position().clone();
-                CellInformation::<f32> {
-                    id: renderer.cell_id(),
-                    position,
-                    radius: renderer.radius_clone(),
-                }
-            })
-            .collect();
-        return Ok(TetGenResult::NoTetGenPossible(information));
+        return Ok(without_triangulation(cells));
     }
     let mut tetgen = Tetgen::new(n_points, None, None, None)?;
     for (index, cell) in cells.iter().enumerate() {
@@ -77,19 +109,7 @@ pub fn delaunay_triangulation(cells: &Vec<Cell>) -> Result<TetGenResult<f32>, St
         Err(err) => {
             if err == "TetGen failed: points are probably coplanar" {
                 println!("Warn: Coplanar cell positions. TetGen not possible.");
-                let information: Vec<CellInformation<f32>> = cells
-                    .iter()
-                    .map(|c| {
-                        let renderer = c.renderer.read().unwrap();
-                        let position = renderer.position().clone();
-                        CellInformation::<f32> {
-                            id: renderer.cell_id(),
-                            position,
-                            radius: renderer.radius_clone(),
-                        }
-                    })
-                    .collect();
-                return Ok(TetGenResult::NoTetGenPossible(information));
+                return Ok(without_triangulation(cells));
             } else {
                 return Err(err);
             }
@@ -115,43 +135,80 @@ pub fn delaunay_triangulation(cells: &Vec<Cell>) -> Result<TetGenResult<f32>, St
         }
         tetraeders.push(TetraederOfCells::new(out.try_into().unwrap()));
     }
-    Ok(TetGenResult::Success(tetraeders))
+    Ok(with_triangulation(tetraeders))
 }
 
-/// If the tetraeder generation was successful:
-/// For each tetraeder where self is included, all other cells are returned.
+/// Returns the other cells that overlap the cell.
 ///
-/// If no tetraeders have been generated:
-/// All other cells where the distance is smaller than the sum of their radi are returned.
+/// If the tetraeder generation was successful only the cells that share a tetraeder with it are checked,
+/// otherwise the cells in the buckets of the spatial hash around it.
 pub fn get_near_cells(
     cell: &CellInformation<f32>,
     tet_gen_result: &TetGenResult<f32>,
 ) -> HashMap<u64, CellInformation<f32>> {
-    let mut near_cells = HashMap::<u64, CellInformation<f32>>::new();
-    match tet_gen_result {
-        TetGenResult::Success(tetraeders) => {
-            tetraeders
-                .iter()
-                .filter(|t| t.nodes().iter().any(|other| other.id == cell.id))
-                .for_each(|tetraeder| {
-                    tetraeder
-                        .nodes()
-                        .iter()
-                        .filter(|other| other.id != cell.id)
-                        .for_each(|other| {
-                            near_cells.insert(other.id, other.clone());
-                        });
-                });
+    let candidates: Box<dyn Iterator<Item = &CellInformation<f32>>> = match tet_gen_result {
+        TetGenResult::Success { neighbours, .. } => {
+            Box::new(neighbours.get(&cell.id).into_iter().flatten())
+        }
+        TetGenResult::NoTetGenPossible(spatial_hash) => {
+            Box::new(spatial_hash.candidates(&cell.position))
         }
-        TetGenResult::NoTetGenPossible(result_cells) => {
-            result_cells
-                .iter()
-                .filter(|other| other.id != cell.id)
-                .filter(|other| near(&cell.position, cell.radius, &other.position, other.radius))
-                .for_each(|other| {
-                    near_cells.insert(other.id, other.clone());
-                });
+    };
+    candidates
+        .filter(|other| other.id != cell.id)
+        .filter(|other| near(&cell.position, cell.radius, &other.position, other.radius))
+        .map(|other| (other.id, other.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(id: u64, x: f32, radius: f32) -> CellInformation<f32> {
+        CellInformation {
+            id,
+            position: Point3::new(x, 0., 0.),
+            radius,
+        }
+    }
+
+    fn near_ids(cell: &CellInformation<f32>, tet_gen_result: &TetGenResult<f32>) -> Vec<u64> {
+        let mut ids: Vec<u64> = get_near_cells(cell, tet_gen_result).into_keys().collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn triangulated_neighbours_must_overlap() {
+        let cells = [
+            cell(0, 0., 1.),
+            cell(1, 1.5, 1.),
+            cell(2, 3., 1.),
+            cell(3, 10., 1.),
+            cell(4, -1.5, 1.),
+        ];
+        let result = with_triangulation(vec![
+            TetraederOfCells::new([0, 1, 2, 3].map(|i| cells[i].clone())),
+            TetraederOfCells::new([0, 1, 3, 4].map(|i| cells[i].clone())),
+        ]);
+        assert_eq!(near_ids(&cells[0], &result), vec![1, 4]);
+        assert_eq!(near_ids(&cells[1], &result), vec![0, 2]);
+        assert!(near_ids(&cells[3], &result).is_empty());
+    }
+
+    #[test]
+    fn both_ways_find_the_same_cells() {
+        let cells = [
+            cell(0, 0., 1.),
+            cell(1, 1.5, 1.),
+            cell(2, 3., 1.),
+            cell(3, 2.2, 0.5),
+        ];
+        let triangulated = with_triangulation(vec![TetraederOfCells::new(cells.clone())]);
+        let hashed = TetGenResult::NoTetGenPossible(SpatialHash::new(cells.to_vec()));
+        for cell in cells.iter() {
+            assert_eq!(near_ids(cell, &triangulated), near_ids(cell, &hashed));
         }
     }
-    near_cells
 }
@@ -0,0 +1,45 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::model::{cell::CellSnapshot, day_night_cycle::DayNightCycle};
+
+/// Number of simulation updates that can be undone.
+pub const HISTORY_LENGTH: usize = 50;
+
+/// The state of all cells before a simulation update.
+pub struct SimulationSnapshot {
+    pub tick: u64,
+    pub day_night_cycle: DayNightCycle,
+    /// the snapshots of the cells by their id
+    pub cells: HashMap<u64, CellSnapshot>,
+}
+
+/// Keeps the last snapshots, the oldest one is dropped when a new one does not fit anymore.
+pub struct History {
+    snapshots: VecDeque<SimulationSnapshot>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: SimulationSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// returns the latest snapshot and removes it from the history
+    pub fn pop(&mut self) -> Option<SimulationSnapshot> {
+        self.snapshots.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
@@ -0,0 +1,21 @@
+use cgmath::Vector3;
+
+/// The light of the sun as the shaders read it.
+// We need this for Rust to store our data correctly for the shaders
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    /// direction the light is coming from
+    direction: [f32; 3],
+    /// between 0 at night and 1 at noon
+    intensity: f32,
+}
+
+impl LightUniform {
+    pub fn new(direction: Vector3<f32>, intensity: f32) -> Self {
+        Self {
+            direction: direction.into(),
+            intensity,
+        }
+    }
+}
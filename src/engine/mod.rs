@@ -1,42 +1,159 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
-use camera::CameraController;
-use delaunay::{delaunay_triangulation, get_near_cells};
+use adhesion::Adhesion;
+use camera::{CameraController, StandardView};
+use capture::{timestamped_path, write_point_cloud, CAPTURE_DIRECTORY, TURNTABLE_FRAMES};
+use cell_renderer::{color_map, stage_color, ColorMode, GeometryMode, CELL_COLOR};
+use cgmath::{Deg, InnerSpace, Point3, Vector3};
+use data_logger::DataLogger;
+use delaunay::{delaunay_triangulation, get_near_cells, without_triangulation, TetGenResult};
 use futures::executor::block_on;
+use history::{History, SimulationSnapshot, HISTORY_LENGTH};
+use relaxation::relax;
+use replay::Replay;
+use selection::Selection;
 use state::ApplicationState;
+use vertex::Vertex;
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, MouseButton, WindowEvent},
+    dpi::PhysicalPosition,
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
     event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
     window::Window,
 };
 
 use crate::{
-    shared::cell::{Cell, EventSystem},
+    model::{
+        day_night_cycle::DayNightCycle,
+        entity::Entity,
+        scenario::{Scenario, SCENARIO_DIRECTORY},
+        settings::SimulationSettings,
+    },
+    shared::{
+        cell::{Cell, CellEvent, CellEventType, CellInformation, EventSystem},
+        math::{mean, radius_from_volume},
+        rng::SimulationRng,
+    },
     SimulationEvent,
 };
 
+mod adhesion;
 mod camera;
+mod capture;
 pub mod cell_renderer;
+pub mod data_logger;
+mod debug_lines;
 mod delaunay;
+mod history;
+mod light;
+mod relaxation;
+pub mod replay;
+mod selection;
+mod spatial_hash;
 mod state;
 mod vertex;
 
-const LEVEL_OF_DETAIL: u16 = 20;
+/// the mouse has to move further than this many pixels while pressed to select with a rectangle
+const DRAG_THRESHOLD: f64 = 5.;
+
+/// What the left mouse button does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tool {
+    /// clicking or dragging a rectangle selects cells
+    Select,
+    /// clicking places a new cell
+    Place,
+    /// dragging moves the selected cells
+    Move,
+    /// clicking cells measures the distance between two and the angle between three of them
+    Measure,
+}
+
+/// The selected cells while they are dragged with the mouse.
+struct MovingCells {
+    /// where the drag started on the plane through the camera target
+    start: Point3<f32>,
+    /// positions of the cells when the drag started
+    positions: Vec<(u64, Point3<f32>)>,
+}
 
 pub struct Simulation<'w> {
+    /// the cells the simulation starts with, also after a reset
+    scenario: Scenario,
+    settings: SimulationSettings,
+    paused: bool,
+    /// number of updates since the start
+    tick: u64,
+    data_logger: DataLogger,
+    geometry_mode: GeometryMode,
+    color_mode: ColorMode,
+    debug_overlay: bool,
+    /// show the reference grid and axes, off at the start because lines are drawn over the cells
+    grid: bool,
+    /// keep the camera focused on the marked cells after every update
+    follow_marked: bool,
+    selection: Selection,
+    tool: Tool,
+    moving_cells: Option<MovingCells>,
+    /// cells clicked with the measure tool, at most three
+    measured: Vec<u64>,
+    /// where the left mouse button has been pressed
+    drag_start: Option<PhysicalPosition<f64>>,
+    modifiers: ModifiersState,
     cells: Arc<Vec<Cell>>,
     cell_events: Arc<EventSystem>,
+    day_night_cycle: DayNightCycle,
+    adhesion: Adhesion,
+    history: History,
+    /// plays back a recording instead of simulating if set
+    replay: Option<Replay>,
+    /// the cells shown in the replay by their id in the recording
+    replayed_cells: HashMap<u64, Cell>,
+    rng: SimulationRng,
     window: Option<Arc<Window>>,
     camera_controller: Arc<Mutex<CameraController>>,
     state: Option<ApplicationState<'w>>,
 }
 
 impl<'w> Simulation<'w> {
-    pub fn new(cells: Vec<Cell>, cell_events: Arc<EventSystem>) -> Self {
+    pub fn new(
+        scenario: Scenario,
+        settings: SimulationSettings,
+        mut rng: SimulationRng,
+        data_logger: DataLogger,
+    ) -> Self {
+        let cell_events = Arc::new(EventSystem::new());
+        let cells = scenario.spawn_cells(&cell_events, &settings, &mut rng);
+        let day_night_cycle = DayNightCycle::new(settings.day_length);
         let simulation = Simulation {
+            scenario,
+            settings,
+            paused: false,
+            tick: 0,
+            data_logger,
+            geometry_mode: GeometryMode::Overlap,
+            color_mode: ColorMode::None,
+            debug_overlay: false,
+            grid: false,
+            follow_marked: false,
+            selection: Selection::new(),
+            tool: Tool::Select,
+            moving_cells: None,
+            measured: Vec::new(),
+            drag_start: None,
+            modifiers: ModifiersState::empty(),
             cells: Arc::new(cells),
             cell_events,
+            day_night_cycle,
+            adhesion: Adhesion::new(),
+            history: History::new(HISTORY_LENGTH),
+            replay: None,
+            replayed_cells: HashMap::new(),
+            rng,
             window: None,
             state: None,
             camera_controller: Arc::new(Mutex::new(CameraController::new(0.2))),
@@ -44,30 +161,70 @@ impl<'w> Simulation<'w> {
         simulation
     }
 
+    /// draws the cells, a frame that cannot be drawn is skipped
     fn render(&self, state: &ApplicationState<'w>) {
-        state.render().unwrap();
+        state.set_light(
+            self.day_night_cycle.light_direction(),
+            self.day_night_cycle.light_intensity(),
+        );
+        if let Err(err) = state.render() {
+            println!("Could not render the cells! Error: {}", err);
+        }
+    }
+
+    /// falls back to finding near cells by distance if the triangulation fails
+    fn triangulate(&self) -> TetGenResult<f32> {
+        match delaunay_triangulation(&self.cells) {
+            Ok(res) => res,
+            Err(err) => {
+                println!("An error occured in the delaunay triangulation!\n{}", err);
+                without_triangulation(&self.cells)
+            }
+        }
     }
 
     pub fn update(&mut self) {
+        if self.replay.is_some() {
+            self.step_replay(1);
+            return;
+        }
+        self.history.push(self.snapshot());
+        self.tick += 1;
+        self.day_night_cycle.advance();
         {
-            let tet_gen_result;
-            {
-                tet_gen_result = match delaunay_triangulation(&self.cells) {
-                    Ok(res) => res,
-                    Err(err) => panic!("An error occured in the delaunay triangulation!\n{}", err),
-                };
+            let tet_gen_result = self.triangulate();
+            let colors = self.cell_colors();
+            let near_cells: Vec<_> = self
+                .cells
+                .iter()
+                .map(|cell| get_near_cells(&cell.clone().into(), &tet_gen_result))
+                .collect();
+            for (cell, near_cells) in self.cells.iter().zip(near_cells.iter()) {
+                let bio = cell.bio.read().unwrap();
+                bio.update(near_cells, &self.day_night_cycle);
             }
-            for cell in self.cells.iter() {
-                let near_cells = get_near_cells(&cell.clone().into(), &tet_gen_result);
-                {
-                    let bio = cell.bio.read().unwrap();
-                    bio.update(&near_cells);
-                }
-                {
-                    let mut renderer = cell.renderer.write().unwrap();
-                    renderer.update(LEVEL_OF_DETAIL, &near_cells);
-                }
+            self.resolve_contacts(&near_cells);
+            for ((cell, color), near_cells) in self.cells.iter().zip(colors).zip(near_cells.iter())
+            {
+                let mut renderer = cell.renderer.write().unwrap();
+                renderer.update(
+                    self.settings.level_of_detail,
+                    self.geometry_mode,
+                    color,
+                    near_cells,
+                );
             }
+            self.update_debug_lines(&tet_gen_result);
+        }
+        if self.follow_marked {
+            self.focus_marked();
+        }
+        let light_intensity = self.day_night_cycle.light_intensity();
+        if let Err(err) = self
+            .data_logger
+            .log(self.tick, &self.cells, light_intensity)
+        {
+            println!("Could not log the simulation data! Error: {}", err);
         }
         match &self.state {
             None => {}
@@ -76,17 +233,704 @@ impl<'w> Simulation<'w> {
             }
         };
     }
+
+    fn snapshot(&self) -> SimulationSnapshot {
+        SimulationSnapshot {
+            tick: self.tick,
+            day_night_cycle: self.day_night_cycle,
+            cells: self
+                .cells
+                .iter()
+                .map(|cell| {
+                    let bio = cell.bio.read().unwrap();
+                    (bio.entity_id(), bio.snapshot())
+                })
+                .collect(),
+        }
+    }
+
+    /// undoes the last update, does nothing if there is no older state left
+    pub fn step_back(&mut self) {
+        if self.replay.is_some() {
+            self.step_replay(-1);
+            return;
+        }
+        let Some(snapshot) = self.history.pop() else {
+            println!("No older simulation state to go back to!");
+            return;
+        };
+        self.tick = snapshot.tick;
+        self.day_night_cycle = snapshot.day_night_cycle;
+        for cell in self.cells.iter() {
+            let bio = cell.bio.read().unwrap();
+            if let Some(cell_snapshot) = snapshot.cells.get(&bio.entity_id()) {
+                bio.restore(cell_snapshot);
+            }
+        }
+        self.redraw_cells();
+    }
+
+    /// rebuilds the geometry of all cells without simulating a step and renders them
+    fn redraw_cells(&mut self) {
+        let tet_gen_result = self.triangulate();
+        let colors = self.cell_colors();
+        for (cell, color) in self.cells.iter().zip(colors) {
+            let near_cells = get_near_cells(&cell.clone().into(), &tet_gen_result);
+            let mut renderer = cell.renderer.write().unwrap();
+            renderer.update(
+                self.settings.level_of_detail,
+                self.geometry_mode,
+                color,
+                &near_cells,
+            );
+        }
+        self.update_debug_lines(&tet_gen_result);
+        if let Some(state) = &self.state {
+            self.render(state);
+        }
+    }
+
+    /// shows the obstacles, the measurement and the grid, as well as the tetraeders of the triangulation
+    /// and the planes between overlapping cells if the debug overlay is enabled
+    fn update_debug_lines(&mut self, tet_gen_result: &TetGenResult<f32>) {
+        let measured: Vec<Point3<f32>> = self
+            .measured
+            .iter()
+            .filter_map(|id| self.cell_position(*id))
+            .collect();
+        let mut timeline = self.selected_timeline();
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        let mut lines = debug_lines::obstacles(&self.scenario.obstacles);
+        if self.grid {
+            lines.append(&mut debug_lines::grid(
+                self.settings.grid_spacing,
+                self.settings.grid_lines,
+            ));
+        }
+        lines.append(&mut debug_lines::measurement(&measured));
+        lines.append(&mut timeline);
+        if self.debug_overlay {
+            let cells: Vec<CellInformation<f32>> =
+                self.cells.iter().map(|cell| cell.clone().into()).collect();
+            lines.append(&mut debug_lines::tetraeder_edges(tet_gen_result));
+            lines.append(&mut debug_lines::overlap_planes(&cells, tet_gen_result));
+        }
+        state.set_debug_lines(lines);
+    }
+
+    /// plots the recorded history of the selected cell next to it,
+    /// only while a recording is replayed and a single cell is selected
+    fn selected_timeline(&self) -> Vec<Vertex> {
+        let Some(replay) = self.replay.as_ref() else {
+            return vec![];
+        };
+        let mut selected = self.selection.cells().iter();
+        let (Some(id), None) = (selected.next(), selected.next()) else {
+            return vec![];
+        };
+        let Some((recorded_id, cell)) = self
+            .replayed_cells
+            .iter()
+            .find(|(_, cell)| cell.bio.read().unwrap().entity_id() == *id)
+        else {
+            return vec![];
+        };
+        let bio = cell.bio.read().unwrap();
+        let radius = radius_from_volume(&bio.volume());
+        let origin = bio.position_clone() + Vector3::unit_x() * 1.5 * radius;
+        debug_lines::timeline(origin, &replay.history(*recorded_id), replay.frame().0)
+    }
+
+    /// colors the cells by the property selected with the color mode,
+    /// relative to the highest value among all cells
+    fn cell_colors(&self) -> Vec<[f32; 3]> {
+        match self.color_mode {
+            ColorMode::None => return vec![CELL_COLOR; self.cells.len()],
+            ColorMode::Stage => {
+                return self
+                    .cells
+                    .iter()
+                    .map(|cell| stage_color(cell.bio.read().unwrap().stage()))
+                    .collect()
+            }
+            _ => {}
+        }
+        let values: Vec<f32> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let bio = cell.bio.read().unwrap();
+                match self.color_mode {
+                    ColorMode::None | ColorMode::Stage => 0.,
+                    ColorMode::Age => bio.age() as f32,
+                    ColorMode::Volume => *bio.volume(),
+                    ColorMode::Carbon => bio.carbon(),
+                }
+            })
+            .collect();
+        let max = values.iter().fold(0., |max: f32, value| max.max(*value));
+        values
+            .iter()
+            .map(|value| {
+                if max > 0. {
+                    color_map(value / max)
+                } else {
+                    color_map(0.)
+                }
+            })
+            .collect()
+    }
+
+    /// plays back the recording from its start instead of simulating
+    pub fn start_replay(&mut self, replay: Replay) {
+        for cell in self.cells.iter() {
+            self.cell_events
+                .unsubscribe(cell.bio.read().unwrap().entity_id());
+        }
+        self.adhesion.clear();
+        self.history.clear();
+        self.replay = Some(replay);
+        self.show_replay_frame();
+    }
+
+    /// moves the replay forward or backward, pausing at its end
+    fn step_replay(&mut self, steps: isize) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        if replay.step(steps) {
+            self.show_replay_frame();
+        } else if steps > 0 {
+            self.paused = true;
+            println!("End of the recording");
+        } else {
+            println!("Start of the recording");
+        }
+    }
+
+    /// jumps to the start of the replay or with `end` to its end
+    fn jump_replay(&mut self, end: bool) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        replay.jump(end);
+        self.show_replay_frame();
+    }
+
+    /// sets the cells to the state of the current update of the replay.
+    /// Cells already shown are kept, so selections and measurements stay on them.
+    fn show_replay_frame(&mut self) {
+        let Some(replay) = self.replay.as_ref() else {
+            return;
+        };
+        let (tick, recorded_cells) = replay.frame();
+        let mut replayed_cells = HashMap::new();
+        let mut cells = vec![];
+        for recorded in recorded_cells {
+            let cell = match self.replayed_cells.remove(&recorded.id) {
+                Some(cell) => cell,
+                None => Cell::new(
+                    recorded.snapshot.position,
+                    recorded.snapshot.volume,
+                    Arc::clone(&self.cell_events),
+                    &self.settings,
+                    &mut self.rng,
+                ),
+            };
+            cell.bio.read().unwrap().restore(&recorded.snapshot);
+            cells.push(cell.clone());
+            replayed_cells.insert(recorded.id, cell);
+        }
+        for cell in self.replayed_cells.values() {
+            self.cell_events
+                .unsubscribe(cell.bio.read().unwrap().entity_id());
+        }
+        self.replayed_cells = replayed_cells;
+        self.tick = tick;
+        self.cells = Arc::new(cells);
+        if let Some(state) = self.state.as_mut() {
+            state.set_cells(Arc::clone(&self.cells));
+        }
+        if self.follow_marked {
+            self.focus_marked();
+        }
+        self.redraw_cells();
+    }
+
+    /// throws away all cells and starts again with the initial cells and the same seed,
+    /// a replay starts again from its beginning
+    pub fn reset(&mut self) {
+        if self.replay.is_some() {
+            self.jump_replay(false);
+            return;
+        }
+        self.rng = SimulationRng::new(self.rng.seed());
+        self.tick = 0;
+        self.day_night_cycle = DayNightCycle::new(self.settings.day_length);
+        self.adhesion.clear();
+        self.history.clear();
+        self.selection = Selection::new();
+        self.measured.clear();
+        self.cell_events = Arc::new(EventSystem::new());
+        self.cells = Arc::new(self.scenario.spawn_cells(
+            &self.cell_events,
+            &self.settings,
+            &mut self.rng,
+        ));
+        if let Some(state) = self.state.as_mut() {
+            state.set_cells(Arc::clone(&self.cells));
+        }
+        self.redraw_cells();
+    }
+
+    /// pulls stuck together cells towards each other, moves overlapping cells apart
+    /// and pushes cells out of the obstacles
+    fn resolve_contacts(&mut self, near_cells: &[HashMap<u64, CellInformation<f32>>]) {
+        let mut information: Vec<CellInformation<f32>> =
+            self.cells.iter().map(|cell| cell.clone().into()).collect();
+        let before: Vec<Point3<f32>> = information.iter().map(|cell| cell.position).collect();
+        self.adhesion
+            .update_contacts(&information, near_cells, self.settings.adhesion_updates);
+        self.adhesion
+            .pull(&mut information, self.settings.adhesion_stiffness);
+        relax(&mut information, self.settings.relaxation_iterations);
+        for cell in information.iter_mut() {
+            for obstacle in self.scenario.obstacles.iter() {
+                cell.position = obstacle.push_out(cell.position, cell.radius);
+            }
+        }
+        for (cell, position) in information.iter().zip(before) {
+            if cell.position != position {
+                self.cell_events.notify(Arc::new(CellEvent {
+                    id: cell.id,
+                    event_type: CellEventType::UpdatePosition(cell.position),
+                }));
+            }
+        }
+    }
+
+    /// adds a new cell to the simulation
+    pub fn place_cell(&mut self, position: Point3<f32>, volume: f32) {
+        if self.replay.is_some() {
+            println!("Cells can not be added to a replay!");
+            return;
+        }
+        let cell = Cell::new(
+            position,
+            volume,
+            Arc::clone(&self.cell_events),
+            &self.settings,
+            &mut self.rng,
+        );
+        let mut cells = self.cells.as_ref().clone();
+        cells.push(cell);
+        self.cells = Arc::new(cells);
+        if let Some(state) = self.state.as_mut() {
+            state.set_cells(Arc::clone(&self.cells));
+        }
+        self.redraw_cells();
+    }
+
+    /// removes the selected cells from the simulation and stops handling their events
+    pub fn delete_selected_cells(&mut self) {
+        let deleted = self.selection.cells().clone();
+        if deleted.is_empty() {
+            return;
+        }
+        if self.replay.is_some() {
+            println!("Cells can not be deleted from a replay!");
+            return;
+        }
+        let cells = self
+            .cells
+            .iter()
+            .filter(|cell| !deleted.contains(&cell.bio.read().unwrap().entity_id()))
+            .cloned()
+            .collect();
+        self.cells = Arc::new(cells);
+        for id in deleted.iter() {
+            self.cell_events.unsubscribe(*id);
+        }
+        self.selection.remove(&deleted);
+        self.measured.clear();
+        self.moving_cells = None;
+        if let Some(state) = self.state.as_mut() {
+            state.set_cells(Arc::clone(&self.cells));
+        }
+        println!("Deleted {} cells", deleted.len());
+        self.redraw_cells();
+    }
+
+    /// remembers where the selected cells are when dragging them starts
+    fn start_moving_cells(&mut self) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let Some(mouse_position) = state.mouse_position else {
+            return;
+        };
+        let selected = self.selection.cells();
+        let positions = self
+            .cells
+            .iter()
+            .map(|cell| cell.bio.read().unwrap())
+            .filter(|bio| selected.contains(&bio.entity_id()))
+            .map(|bio| (bio.entity_id(), bio.position_clone()))
+            .collect();
+        self.moving_cells = Some(MovingCells {
+            start: state.screen_pos_2_world_pos(&mouse_position),
+            positions,
+        });
+    }
+
+    /// moves the dragged cells by the distance the mouse moved since the start of the drag
+    fn move_cells(&mut self, mouse_position: &PhysicalPosition<f64>) {
+        let (Some(state), Some(moving_cells)) = (&self.state, &self.moving_cells) else {
+            return;
+        };
+        let offset = state.screen_pos_2_world_pos(mouse_position) - moving_cells.start;
+        for (id, position) in moving_cells.positions.iter() {
+            self.cell_events.notify(Arc::new(CellEvent {
+                id: *id,
+                event_type: CellEventType::UpdatePosition(position + offset),
+            }));
+        }
+        self.redraw_cells();
+    }
+
+    fn switch_tool(&mut self, tool: Tool) {
+        self.tool = if self.tool == tool {
+            Tool::Select
+        } else {
+            tool
+        };
+        println!("Tool: {:?}", self.tool);
+        if !self.measured.is_empty() {
+            self.measured.clear();
+            self.redraw_cells();
+        }
+    }
+
+    fn cell_position(&self, id: u64) -> Option<Point3<f32>> {
+        self.cells
+            .iter()
+            .map(|cell| cell.bio.read().unwrap())
+            .find(|bio| bio.entity_id() == id)
+            .map(|bio| bio.position_clone())
+    }
+
+    /// adds the cell to the measurement and prints the distance or angle once there are enough cells
+    /// together with the height of all cells
+    fn measure(&mut self, id: u64) {
+        if self.measured.len() == 3 {
+            self.measured.clear();
+        }
+        self.measured.push(id);
+        // a measured cell may have been deleted or left the replay since it was picked
+        let complete = match *self.measured.as_slice() {
+            [first, second] => match (self.cell_position(first), self.cell_position(second)) {
+                (Some(a), Some(b)) => {
+                    println!(
+                        "Distance between cells {} and {}: {}",
+                        first,
+                        second,
+                        (b - a).magnitude()
+                    );
+                    true
+                }
+                _ => false,
+            },
+            [first, vertex, last] => match (
+                self.cell_position(first),
+                self.cell_position(vertex),
+                self.cell_position(last),
+            ) {
+                (Some(a), Some(b), Some(c)) => {
+                    println!(
+                        "Angle at cell {}: {}°",
+                        vertex,
+                        Deg::from((a - b).angle(c - b)).0
+                    );
+                    true
+                }
+                _ => false,
+            },
+            _ => {
+                println!("Measuring from cell {}", id);
+                true
+            }
+        };
+        if !complete {
+            println!("A measured cell does not exist anymore, the measurement is cleared");
+            self.measured.clear();
+        }
+        println!("Height of all cells: {}", self.height());
+        self.redraw_cells();
+    }
+
+    /// distance from the lowest to the highest point of all cells
+    fn height(&self) -> f32 {
+        let (bottom, top) =
+            self.cells
+                .iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(bottom, top), cell| {
+                    let renderer = cell.renderer.read().unwrap();
+                    let y = renderer.position_clone().y;
+                    let radius = renderer.radius_clone();
+                    (bottom.min(y - radius), top.max(y + radius))
+                });
+        (top - bottom).max(0.)
+    }
+
+    /// returns the center of all marked cells or None if no cell is marked
+    fn marked_center(&self) -> Option<Point3<f32>> {
+        let selected = self.selection.cells();
+        let positions: Vec<Point3<f32>> = self
+            .cells
+            .iter()
+            .map(|cell| cell.renderer.read().unwrap())
+            .filter(|renderer| selected.contains(&renderer.cell_id()))
+            .map(|renderer| renderer.position_clone())
+            .collect();
+        if positions.is_empty() {
+            return None;
+        }
+        Some(mean(&positions))
+    }
+
+    /// points the camera at the center of the marked cells, returns false if no cell is marked
+    fn focus_marked(&mut self) -> bool {
+        let Some(center) = self.marked_center() else {
+            return false;
+        };
+        if let Some(state) = self.state.as_mut() {
+            state.focus(center);
+        }
+        true
+    }
+
+    /// Space pauses and resumes, N simulates a single step while paused,
+    /// B goes back a step while paused, R resets, G switches the geometry mode
+    /// C switches the color mode, T toggles the debug overlay, X toggles the grid,
+    /// P takes a screenshot, O records a turntable, F focuses the camera on the marked cells
+    /// and Shift+F toggles following them.
+    /// Ctrl and a number key stores the selected cells as group, the number key selects the group again.
+    /// I toggles placing cells with left clicks, M toggles moving the selected cells by dragging
+    /// and L toggles measuring between clicked cells.
+    /// Delete removes the selected cells, E exports the cells as scenario file
+    /// and Shift+E as PLY point cloud.
+    /// Numpad 5 toggles the orthographic projection, Numpad 7, 1 and 3 look from the top, front and side.
+    /// V splits the window into a second view from the top, the camera under the mouse is the one controlled.
+    /// While replaying, N and B step through the recording, R and Home jump to its start and End to its end.
+    /// Returns whether the key has been handled.
+    fn process_simulation_keys(&mut self, event: &KeyEvent) -> bool {
+        let PhysicalKey::Code(keycode) = event.physical_key else {
+            return false;
+        };
+        if event.state != ElementState::Pressed || event.repeat {
+            return false;
+        }
+        match keycode {
+            KeyCode::Space => {
+                self.paused = !self.paused;
+                println!(
+                    "Simulation {}",
+                    if self.paused { "paused" } else { "resumed" }
+                );
+                true
+            }
+            KeyCode::KeyN => {
+                if self.paused {
+                    self.update();
+                }
+                true
+            }
+            KeyCode::KeyB => {
+                if self.paused {
+                    self.step_back();
+                }
+                true
+            }
+            KeyCode::Home | KeyCode::End => {
+                self.jump_replay(keycode == KeyCode::End);
+                true
+            }
+            KeyCode::KeyR => {
+                println!("Simulation reset");
+                self.reset();
+                true
+            }
+            KeyCode::KeyG => {
+                self.geometry_mode = self.geometry_mode.next();
+                println!("Geometry mode: {:?}", self.geometry_mode);
+                self.redraw_cells();
+                true
+            }
+            KeyCode::KeyC => {
+                self.color_mode = self.color_mode.next();
+                println!("Color mode: {:?}", self.color_mode);
+                self.redraw_cells();
+                true
+            }
+            KeyCode::KeyT => {
+                self.debug_overlay = !self.debug_overlay;
+                self.redraw_cells();
+                true
+            }
+            KeyCode::KeyX => {
+                self.grid = !self.grid;
+                if self.grid {
+                    println!("Grid spacing: {}", self.settings.grid_spacing);
+                }
+                self.redraw_cells();
+                true
+            }
+            KeyCode::KeyF => {
+                if self.modifiers.shift_key() {
+                    self.follow_marked = !self.follow_marked;
+                    println!(
+                        "Following marked cells {}",
+                        if self.follow_marked { "on" } else { "off" }
+                    );
+                }
+                if !self.focus_marked() {
+                    println!("No cell is marked!");
+                }
+                if let Some(state) = &self.state {
+                    self.render(state);
+                }
+                true
+            }
+            KeyCode::Digit1
+            | KeyCode::Digit2
+            | KeyCode::Digit3
+            | KeyCode::Digit4
+            | KeyCode::Digit5
+            | KeyCode::Digit6
+            | KeyCode::Digit7
+            | KeyCode::Digit8
+            | KeyCode::Digit9 => {
+                let group = selection_group(keycode);
+                if self.modifiers.control_key() {
+                    self.selection.store_group(group);
+                    println!("Stored selection group {}", group);
+                } else if self.selection.recall_group(group, &self.cell_events) {
+                    self.redraw_cells();
+                } else {
+                    println!("No selection group {}!", group);
+                }
+                true
+            }
+            KeyCode::Delete => {
+                self.delete_selected_cells();
+                true
+            }
+            KeyCode::KeyE if self.modifiers.shift_key() => {
+                let path = timestamped_path(CAPTURE_DIRECTORY, "cells", ".ply");
+                match write_point_cloud(&path, &self.cells) {
+                    Ok(_) => println!("Saved point cloud to {}", path.display()),
+                    Err(err) => println!("Could not save point cloud! Error: {}", err),
+                }
+                true
+            }
+            KeyCode::KeyE => {
+                let path = timestamped_path(SCENARIO_DIRECTORY, "scenario", ".ron");
+                match self.scenario.with_cells(&self.cells).write(&path) {
+                    Ok(_) => println!("Saved scenario to {}", path.display()),
+                    Err(err) => println!("Could not save scenario! Error: {}", err),
+                }
+                true
+            }
+            KeyCode::KeyI => {
+                self.switch_tool(Tool::Place);
+                true
+            }
+            KeyCode::KeyM => {
+                self.switch_tool(Tool::Move);
+                true
+            }
+            KeyCode::KeyL => {
+                self.switch_tool(Tool::Measure);
+                true
+            }
+            KeyCode::KeyV => {
+                if let Some(state) = self.state.as_mut() {
+                    let split = state.toggle_split_screen();
+                    println!("Split screen {}", if split { "on" } else { "off" });
+                }
+                if let Some(state) = &self.state {
+                    self.render(state);
+                }
+                true
+            }
+            KeyCode::Numpad5 => {
+                if let Some(state) = self.state.as_mut() {
+                    let orthographic = state.toggle_orthographic();
+                    println!(
+                        "Projection: {}",
+                        if orthographic {
+                            "orthographic"
+                        } else {
+                            "perspective"
+                        }
+                    );
+                }
+                if let Some(state) = &self.state {
+                    self.render(state);
+                }
+                true
+            }
+            KeyCode::Numpad7 | KeyCode::Numpad1 | KeyCode::Numpad3 => {
+                let view = match keycode {
+                    KeyCode::Numpad7 => StandardView::Top,
+                    KeyCode::Numpad1 => StandardView::Front,
+                    _ => StandardView::Side,
+                };
+                if let Some(state) = self.state.as_mut() {
+                    state.look_from(view);
+                }
+                if let Some(state) = &self.state {
+                    self.render(state);
+                }
+                true
+            }
+            KeyCode::KeyP => {
+                if let Some(state) = &self.state {
+                    let path = timestamped_path(CAPTURE_DIRECTORY, "screenshot", ".png");
+                    match state.screenshot(&path) {
+                        Ok(_) => println!("Saved screenshot to {}", path.display()),
+                        Err(err) => println!("Could not save screenshot! Error: {}", err),
+                    }
+                }
+                true
+            }
+            KeyCode::KeyO => {
+                if let Some(state) = self.state.as_mut() {
+                    let directory = timestamped_path(CAPTURE_DIRECTORY, "turntable", "");
+                    match state.record_turntable(&directory, TURNTABLE_FRAMES) {
+                        Ok(_) => println!("Saved turntable to {}", directory.display()),
+                        Err(err) => println!("Could not record turntable! Error: {}", err),
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<'w> ApplicationHandler<SimulationEvent> for Simulation<'w> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let window = Arc::new(init_window(event_loop));
+        let title = format!("Plant Simulation (seed {})", self.rng.seed());
+        let window = Arc::new(init_window(event_loop, &title));
         self.window = Some(window.clone());
         let cells = Arc::clone(&self.cells);
         let state = block_on(ApplicationState::new(
             window,
+            title,
             cells,
-            Arc::clone(&self.cell_events),
             self.camera_controller.clone(),
         ));
         self.state = Some(state);
@@ -96,7 +940,9 @@ impl<'w> ApplicationHandler<SimulationEvent> for Simulation<'w> {
     fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: SimulationEvent) {
         match event {
             SimulationEvent::Update => {
-                self.update();
+                if !self.paused {
+                    self.update();
+                }
             }
         }
     }
@@ -114,7 +960,9 @@ impl<'w> ApplicationHandler<SimulationEvent> for Simulation<'w> {
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested { .. } => {
-                let state = self.state.as_mut().unwrap();
+                let Some(state) = self.state.as_mut() else {
+                    return;
+                };
                 state.update_camera();
                 let state = self.state.as_ref().unwrap();
                 self.render(state);
@@ -125,20 +973,27 @@ impl<'w> ApplicationHandler<SimulationEvent> for Simulation<'w> {
                 // self.window.as_ref().unwrap().clone().request_redraw();
             }
             WindowEvent::Resized { .. } => {
-                let state = self.state.as_mut().unwrap();
+                let Some(state) = self.state.as_mut() else {
+                    return;
+                };
                 state.resize();
                 state.update_camera();
                 let state = self.state.as_ref().unwrap();
                 self.render(state);
             }
             WindowEvent::KeyboardInput { event, .. } => {
+                if self.process_simulation_keys(&event) {
+                    return;
+                }
                 let camera_controller = Arc::clone(&self.camera_controller);
                 let _ = camera_controller
                     .lock()
                     .as_mut()
                     .unwrap()
                     .process_events(&event);
-                let state = self.state.as_mut().unwrap();
+                let Some(state) = self.state.as_mut() else {
+                    return;
+                };
                 state.update_camera();
                 let state = self.state.as_ref().unwrap();
                 self.render(state);
@@ -149,46 +1004,133 @@ impl<'w> ApplicationHandler<SimulationEvent> for Simulation<'w> {
                 ..
             } => match button {
                 MouseButton::Left => match key_state {
+                    ElementState::Pressed => {
+                        self.drag_start = self.state.as_ref().and_then(|s| s.mouse_position);
+                        if self.tool == Tool::Move {
+                            self.start_moving_cells();
+                        }
+                    }
+                    ElementState::Released if self.tool == Tool::Move => {
+                        self.drag_start = None;
+                        self.moving_cells = None;
+                    }
+                    ElementState::Released if self.tool == Tool::Measure => {
+                        self.drag_start = None;
+                        let Some(state) = &self.state else {
+                            println!("No state!");
+                            return;
+                        };
+                        let Some(position) = state.mouse_position.as_ref() else {
+                            return;
+                        };
+                        let select_ray = state.screen_pos_2_select_ray(position);
+                        if let Some(id) = state.cells_hit(select_ray).first().copied() {
+                            self.measure(id);
+                        }
+                    }
+                    ElementState::Released if self.tool == Tool::Place => {
+                        self.drag_start = None;
+                        let Some(state) = &self.state else {
+                            println!("No state!");
+                            return;
+                        };
+                        let Some(position) = state.mouse_position.as_ref() else {
+                            return;
+                        };
+                        let position = state.screen_pos_2_world_pos(position);
+                        println!("Placing cell at {:?}", position);
+                        self.place_cell(position, self.settings.placed_cell_volume);
+                    }
                     ElementState::Released => {
-                        match &self.state {
+                        let drag_start = self.drag_start.take();
+                        let hits = match &self.state {
                             Some(state) => {
-                                let position = state.mouse_position.as_ref().unwrap();
-                                let select_ray = state.screen_pos_2_select_ray(position);
-                                state.select_cells(select_ray);
+                                let Some(position) = state.mouse_position.as_ref() else {
+                                    return;
+                                };
+                                match drag_start {
+                                    Some(start)
+                                        if (start.x - position.x).abs() > DRAG_THRESHOLD
+                                            || (start.y - position.y).abs() > DRAG_THRESHOLD =>
+                                    {
+                                        state.cells_in_rectangle(&start, position)
+                                    }
+                                    _ => {
+                                        // only the closest cell, not the ones hidden behind it
+                                        let select_ray = state.screen_pos_2_select_ray(position);
+                                        state
+                                            .cells_hit(select_ray)
+                                            .first()
+                                            .copied()
+                                            .into_iter()
+                                            .collect()
+                                    }
+                                }
                             }
                             None => {
-                                println!("No state!")
+                                println!("No state!");
+                                return;
                             }
+                        };
+                        if self.modifiers.shift_key() {
+                            self.selection.add(hits, &self.cell_events);
+                        } else {
+                            self.selection.set(hits, &self.cell_events);
                         }
+                        self.redraw_cells();
                         // TODO: if a cell has been hit with this position, set the cell as acive and use its center as camera center.
                     }
-                    _ => {}
                 },
                 _ => {}
             },
             WindowEvent::MouseWheel { delta, phase, .. } => {
-                println!("Got mouse wheel event: {:?}, {:?}", delta, phase);
                 let camera_controller = Arc::clone(&self.camera_controller);
                 let _ = camera_controller
                     .lock()
                     .as_mut()
                     .unwrap()
                     .process_mouse_wheel(&delta, &phase);
-                let state = self.state.as_mut().unwrap();
+                let Some(state) = self.state.as_mut() else {
+                    return;
+                };
                 state.update_camera();
                 let state = self.state.as_ref().unwrap();
                 self.render(state);
             }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
             WindowEvent::CursorMoved { position, .. } => {
-                self.state.as_mut().unwrap().mouse_position = Some(position);
+                if let Some(state) = self.state.as_mut() {
+                    state.mouse_position = Some(position);
+                }
+                if self.moving_cells.is_some() {
+                    self.move_cells(&position);
+                }
             }
             _ => {}
         }
     }
 }
 
-fn init_window(event_loop: &ActiveEventLoop) -> Window {
-    let window_attributes = Window::default_attributes().with_title("Plant Simulation");
+/// the number of the digit key
+fn selection_group(keycode: KeyCode) -> u8 {
+    match keycode {
+        KeyCode::Digit1 => 1,
+        KeyCode::Digit2 => 2,
+        KeyCode::Digit3 => 3,
+        KeyCode::Digit4 => 4,
+        KeyCode::Digit5 => 5,
+        KeyCode::Digit6 => 6,
+        KeyCode::Digit7 => 7,
+        KeyCode::Digit8 => 8,
+        KeyCode::Digit9 => 9,
+        _ => 0,
+    }
+}
+
+fn init_window(event_loop: &ActiveEventLoop, title: &str) -> Window {
+    let window_attributes = Window::default_attributes().with_title(title);
     event_loop
         .create_window(window_attributes)
         .expect("Window creation for winit failed.")
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::shared::cell::CellInformation;
+
+use super::spatial_hash::SpatialHash;
+
+/// Pushes overlapping cells apart until their centers are at least as far apart as the larger radius.
+/// Each pair is corrected right away, so the following pairs already see the new positions.
+/// A cell moves less the more volume it has compared to the other cell.
+/// Stops early once no pair overlaps anymore.
+pub fn relax(cells: &mut [CellInformation<f32>], iterations: u32) {
+    let index_by_id: HashMap<u64, usize> = cells
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| (cell.id, index))
+        .collect();
+    for _ in 0..iterations {
+        let spatial_hash = SpatialHash::new(cells.to_vec());
+        let mut moved = false;
+        for i in 0..cells.len() {
+            let others: Vec<usize> = spatial_hash
+                .candidates(&cells[i].position)
+                .map(|other| index_by_id[&other.id])
+                .filter(|&j| j > i)
+                .collect();
+            for j in others {
+                let offset = cells[i].position - cells[j].position;
+                let distance = offset.magnitude();
+                let target = cells[i].radius.max(cells[j].radius);
+                if distance >= target {
+                    continue;
+                }
+                // cells at the same position are pushed apart along an arbitrary axis
+                let direction = if distance > 0. {
+                    offset / distance
+                } else {
+                    Vector3::unit_x()
+                };
+                let weight_i = cells[i].radius.powi(3);
+                let weight_j = cells[j].radius.powi(3);
+                let share_i = if weight_i + weight_j > 0. {
+                    weight_j / (weight_i + weight_j)
+                } else {
+                    0.5
+                };
+                let correction = direction * (target - distance);
+                cells[i].position += correction * share_i;
+                cells[j].position -= correction * (1. - share_i);
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+
+    use super::*;
+    use crate::shared::math::distance;
+
+    fn cell(id: u64, x: f32, radius: f32) -> CellInformation<f32> {
+        CellInformation {
+            id,
+            position: Point3::new(x, 0., 0.),
+            radius,
+        }
+    }
+
+    #[test]
+    fn overlapping_cells_are_pushed_apart() {
+        let mut cells = vec![cell(0, 0., 1.), cell(1, 0.5, 1.)];
+        relax(&mut cells, 1);
+        assert!((distance(&cells[0].position, &cells[1].position) - 1.).abs() < 1e-5);
+        // cells of the same size move by the same amount
+        assert!((cells[0].position.x + 0.25).abs() < 1e-5);
+        assert!((cells[1].position.x - 0.75).abs() < 1e-5);
+    }
+
+    #[test]
+    fn the_smaller_cell_moves_further() {
+        let mut cells = vec![cell(0, 0., 2.), cell(1, 1., 1.)];
+        relax(&mut cells, 1);
+        assert!((distance(&cells[0].position, &cells[1].position) - 2.).abs() < 1e-5);
+        let big_moved = cells[0].position.x.abs();
+        let small_moved = cells[1].position.x - 1.;
+        assert!(small_moved > big_moved);
+    }
+
+    #[test]
+    fn cells_at_the_same_position_are_separated() {
+        let mut cells = vec![cell(0, 3., 1.), cell(1, 3., 1.)];
+        relax(&mut cells, 1);
+        assert!((distance(&cells[0].position, &cells[1].position) - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cells_far_enough_apart_stay_put() {
+        // closer than the sum of their radii but not closer than the larger radius
+        let cells = vec![cell(0, 0., 1.), cell(1, 1.5, 1.), cell(2, 10., 1.)];
+        let mut relaxed = cells.clone();
+        relax(&mut relaxed, 4);
+        for (before, after) in cells.iter().zip(relaxed.iter()) {
+            assert_eq!(before.position, after.position);
+        }
+    }
+}
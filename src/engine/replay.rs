@@ -0,0 +1,190 @@
+use std::{error::Error, path::Path};
+
+use cgmath::Point3;
+
+use crate::model::cell::CellSnapshot;
+
+/// A recorded cell in one update, the id is the one it had while recording.
+pub struct RecordedCell {
+    pub id: u64,
+    pub snapshot: CellSnapshot,
+}
+
+/// The cells of every update of a run written with `--cells-csv`, played back without simulating.
+pub struct Replay {
+    /// the tick of each update with its cells
+    frames: Vec<(u64, Vec<RecordedCell>)>,
+    current: usize,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Reads the csv by the names in its header.
+    /// Position and volume are needed, carbon and ages are 0 if their column is missing.
+    fn parse(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = content.lines();
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or("The recording is empty!")?
+            .split(',')
+            .map(|name| name.trim())
+            .collect();
+        let column = |name: &str| header.iter().position(|column| *column == name);
+        let required = |name: &str| -> Result<usize, Box<dyn Error>> {
+            column(name).ok_or_else(|| format!("The recording has no {} column!", name).into())
+        };
+        let tick = required("tick")?;
+        let id = required("cell_id")?;
+        let [x, y, z, volume] = [
+            required("x")?,
+            required("y")?,
+            required("z")?,
+            required("volume")?,
+        ];
+        let [carbon, time_lived, age] = [column("carbon"), column("time_lived"), column("age")];
+
+        let mut frames: Vec<(u64, Vec<RecordedCell>)> = vec![];
+        for (number, line) in lines.enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values: Vec<&str> = line.split(',').map(|value| value.trim()).collect();
+            let value = |index: usize| -> Result<&str, Box<dyn Error>> {
+                values.get(index).copied().ok_or_else(|| {
+                    format!("Line {} of the recording is too short!", number + 2).into()
+                })
+            };
+            let float =
+                |index: usize| -> Result<f32, Box<dyn Error>> { Ok(value(index)?.parse()?) };
+            let optional = |index: Option<usize>| -> Result<u32, Box<dyn Error>> {
+                match index {
+                    Some(index) => Ok(value(index)?.parse()?),
+                    None => Ok(0),
+                }
+            };
+            let cell = RecordedCell {
+                id: value(id)?.parse()?,
+                snapshot: CellSnapshot {
+                    time_lived: optional(time_lived)?,
+                    age: optional(age)?,
+                    carbon: match carbon {
+                        Some(carbon) => float(carbon)?,
+                        None => 0.,
+                    },
+                    position: Point3::new(float(x)?, float(y)?, float(z)?),
+                    volume: float(volume)?,
+                },
+            };
+            let tick: u64 = value(tick)?.parse()?;
+            match frames.last_mut() {
+                Some((last_tick, cells)) if *last_tick == tick => cells.push(cell),
+                _ => frames.push((tick, vec![cell])),
+            }
+        }
+        if frames.is_empty() {
+            return Err("The recording has no cells!".into());
+        }
+        Ok(Self { frames, current: 0 })
+    }
+
+    /// the tick and the cells of the update that is shown
+    pub fn frame(&self) -> (u64, &[RecordedCell]) {
+        let (tick, cells) = &self.frames[self.current];
+        (*tick, cells)
+    }
+
+    /// moves the given number of updates forward or backward,
+    /// returns false if the start or the end of the recording has already been reached
+    pub fn step(&mut self, steps: isize) -> bool {
+        let target = self
+            .current
+            .saturating_add_signed(steps)
+            .min(self.frames.len() - 1);
+        let moved = target != self.current;
+        self.current = target;
+        moved
+    }
+
+    /// jumps to the first update or with `end` to the last one
+    pub fn jump(&mut self, end: bool) {
+        self.current = if end { self.frames.len() - 1 } else { 0 };
+    }
+
+    /// the tick and the recorded state of the cell in every update it was recorded in
+    pub fn history(&self, id: u64) -> Vec<(u64, CellSnapshot)> {
+        self.frames
+            .iter()
+            .filter_map(|(tick, cells)| {
+                let cell = cells.iter().find(|cell| cell.id == id)?;
+                Some((*tick, cell.snapshot))
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDING: &str = "tick,cell_id,x,y,z,volume,carbon,time_lived,age
+1,7,0,1,2,3,0.5,1,1
+1,8,1,1,1,1,0.25,0,1
+2,7,0,2,2,3.5,0.4,2,2
+";
+
+    #[test]
+    fn rows_are_grouped_by_tick() {
+        let replay = Replay::parse(RECORDING).unwrap();
+        assert_eq!(replay.len(), 2);
+        let (tick, cells) = replay.frame();
+        assert_eq!(tick, 1);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].id, 7);
+        assert_eq!(cells[0].snapshot.position, Point3::new(0., 1., 2.));
+        assert_eq!(cells[1].snapshot.carbon, 0.25);
+    }
+
+    #[test]
+    fn missing_ages_are_zero() {
+        let replay = Replay::parse("tick,cell_id,x,y,z,volume\n1,7,0,0,0,1\n").unwrap();
+        let (_, cells) = replay.frame();
+        assert_eq!(cells[0].snapshot.age, 0);
+        assert_eq!(cells[0].snapshot.carbon, 0.);
+    }
+
+    #[test]
+    fn missing_position_is_an_error() {
+        assert!(Replay::parse("tick,cell_id,x,y,volume\n1,7,0,0,1\n").is_err());
+        assert!(Replay::parse("tick,cell_id,x,y,z,volume\n").is_err());
+    }
+
+    #[test]
+    fn steps_stop_at_the_ends() {
+        let mut replay = Replay::parse(RECORDING).unwrap();
+        assert!(!replay.step(-1));
+        assert!(replay.step(5));
+        assert_eq!(replay.frame().0, 2);
+        assert!(!replay.step(1));
+        replay.jump(false);
+        assert_eq!(replay.frame().0, 1);
+    }
+
+    #[test]
+    fn history_has_the_updates_the_cell_was_recorded_in() {
+        let replay = Replay::parse(RECORDING).unwrap();
+        let history = replay.history(7);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].0, 2);
+        assert_eq!(history[1].1.volume, 3.5);
+        assert_eq!(replay.history(8).len(), 1);
+        assert!(replay.history(9).is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::shared::cell::{CellEvent, CellEventType, EventSystem};
+
+/// The ids of the selected cells and stored selection groups.
+/// Every change is sent as mark event to the cells, so they are rendered highlighted.
+pub struct Selection {
+    selected: HashSet<u64>,
+    groups: HashMap<u8, HashSet<u64>>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self {
+            selected: HashSet::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    pub fn cells(&self) -> &HashSet<u64> {
+        &self.selected
+    }
+
+    /// selects exactly the given cells
+    pub fn set(&mut self, ids: impl IntoIterator<Item = u64>, events: &EventSystem) {
+        let ids: HashSet<u64> = ids.into_iter().collect();
+        for id in self.selected.difference(&ids) {
+            mark(events, *id, false);
+        }
+        for id in ids.difference(&self.selected) {
+            mark(events, *id, true);
+        }
+        self.selected = ids;
+    }
+
+    /// adds the given cells to the selection
+    pub fn add(&mut self, ids: impl IntoIterator<Item = u64>, events: &EventSystem) {
+        for id in ids {
+            if self.selected.insert(id) {
+                mark(events, id, true);
+            }
+        }
+    }
+
+    /// forgets the cells in the selection and in all groups, e.g. because they have been deleted
+    pub fn remove(&mut self, ids: &HashSet<u64>) {
+        self.selected.retain(|id| !ids.contains(id));
+        for group in self.groups.values_mut() {
+            group.retain(|id| !ids.contains(id));
+        }
+    }
+
+    /// remembers the current selection under the group number
+    pub fn store_group(&mut self, group: u8) {
+        self.groups.insert(group, self.selected.clone());
+    }
+
+    /// selects the cells stored under the group number, returns false if there is no such group
+    pub fn recall_group(&mut self, group: u8, events: &EventSystem) -> bool {
+        let Some(ids) = self.groups.get(&group).cloned() else {
+            return false;
+        };
+        self.set(ids, events);
+        true
+    }
+}
+
+fn mark(events: &EventSystem, id: u64, marked: bool) {
+    events.notify(Arc::new(CellEvent {
+        id,
+        event_type: CellEventType::Mark(Some(marked)),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// an event system that records the latest mark of each of the cells
+    fn events(ids: &[u64]) -> (EventSystem, Arc<Mutex<HashMap<u64, bool>>>) {
+        let events = EventSystem::new();
+        let marks = Arc::new(Mutex::new(HashMap::new()));
+        for id in ids {
+            let marks = Arc::clone(&marks);
+            events.subscribe(*id, move |event| {
+                if let CellEventType::Mark(Some(marked)) = event.event_type {
+                    marks.lock().unwrap().insert(event.id, marked);
+                }
+            });
+        }
+        (events, marks)
+    }
+
+    fn ids(ids: &[u64]) -> HashSet<u64> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn set_replaces_the_selection_and_marks_the_changes() {
+        let (events, marks) = events(&[1, 2, 3]);
+        let mut selection = Selection::new();
+        selection.set([1, 2], &events);
+        selection.set([2, 3], &events);
+        assert_eq!(selection.cells(), &ids(&[2, 3]));
+        let marks = marks.lock().unwrap();
+        assert!(!marks[&1]);
+        assert!(marks[&2]);
+        assert!(marks[&3]);
+    }
+
+    #[test]
+    fn add_keeps_the_selection() {
+        let (events, marks) = events(&[1, 2]);
+        let mut selection = Selection::new();
+        selection.set([1], &events);
+        selection.add([2], &events);
+        assert_eq!(selection.cells(), &ids(&[1, 2]));
+        assert!(marks.lock().unwrap()[&2]);
+    }
+
+    #[test]
+    fn remove_forgets_cells_in_selection_and_groups() {
+        let (events, _) = events(&[1, 2]);
+        let mut selection = Selection::new();
+        selection.set([1, 2], &events);
+        selection.store_group(1);
+        selection.remove(&ids(&[1]));
+        assert_eq!(selection.cells(), &ids(&[2]));
+        selection.set([], &events);
+        assert!(selection.recall_group(1, &events));
+        assert_eq!(selection.cells(), &ids(&[2]));
+    }
+
+    #[test]
+    fn recall_group_selects_the_stored_cells() {
+        let (events, marks) = events(&[1, 2]);
+        let mut selection = Selection::new();
+        selection.set([1], &events);
+        selection.store_group(3);
+        selection.set([2], &events);
+        assert!(selection.recall_group(3, &events));
+        assert_eq!(selection.cells(), &ids(&[1]));
+        assert!(marks.lock().unwrap()[&1]);
+        assert!(!marks.lock().unwrap()[&2]);
+        assert!(!selection.recall_group(4, &events));
+    }
+}
@@ -6,6 +6,18 @@ struct CameraUniform {
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+struct LightUniform {
+    // direction the light is coming from
+    direction: vec3<f32>,
+    // between 0 at night and 1 at noon
+    intensity: f32,
+};
+@group(1) @binding(0)
+var<uniform> light: LightUniform;
+
+// share of the light that reaches every face, so cells stay visible at night
+const AMBIENT: f32 = 0.3;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
@@ -14,6 +26,7 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) world_position: vec3<f32>,
 };
 
 @vertex
@@ -22,6 +35,7 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.color = model.color;
+    out.world_position = model.position;
     // Multiplication order is important when it comes to matrices.
     // The vector goes on the right, and the matrices go on the left in order of importance.
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
@@ -32,5 +46,15 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the vertices have no normals, the normal of the triangle is taken from how the position changes on screen.
+    // Screen y points down, so this order points to the camera on the front faces that are not culled.
+    let normal = normalize(cross(dpdy(in.world_position), dpdx(in.world_position)));
+    let diffuse = max(dot(normal, normalize(light.direction)), 0.0) * light.intensity;
+    return vec4<f32>(in.color * (AMBIENT + (1.0 - AMBIENT) * diffuse), 0.5);
+}
+
+// lines have no faces to light
+@fragment
+fn fs_line(in: VertexOutput) -> @location(0) vec4<f32> {
     return vec4<f32>(in.color, 0.5);
 }
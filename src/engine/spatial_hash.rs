@@ -0,0 +1,97 @@
+use crate::shared::cell::CellInformation;
+use cgmath::{BaseFloat, Point3};
+use std::collections::HashMap;
+
+/// Sorts cells into buckets of a uniform grid by their center.
+/// The buckets are as large as the biggest cell diameter,
+/// so overlapping cells are always in the same or in adjacent buckets.
+pub struct SpatialHash<T: BaseFloat> {
+    bucket_size: T,
+    buckets: HashMap<(i32, i32, i32), Vec<CellInformation<T>>>,
+}
+
+impl<T: BaseFloat> SpatialHash<T> {
+    pub fn new(cells: Vec<CellInformation<T>>) -> Self {
+        let max_radius = cells
+            .iter()
+            .map(|c| c.radius)
+            .fold(T::zero(), |max, radius| max.max(radius));
+        let bucket_size = if max_radius > T::zero() {
+            max_radius + max_radius
+        } else {
+            T::one()
+        };
+        let mut spatial_hash = Self {
+            bucket_size,
+            buckets: HashMap::new(),
+        };
+        for cell in cells {
+            let key = spatial_hash.key(&cell.position);
+            spatial_hash.buckets.entry(key).or_default().push(cell);
+        }
+        spatial_hash
+    }
+
+    fn key(&self, position: &Point3<T>) -> (i32, i32, i32) {
+        let index = |value: T| (value / self.bucket_size).floor().to_i32().unwrap_or(0);
+        (index(position.x), index(position.y), index(position.z))
+    }
+
+    /// returns all cells in the bucket of the position and in the 26 buckets around it
+    pub fn candidates(&self, position: &Point3<T>) -> impl Iterator<Item = &CellInformation<T>> {
+        let (x, y, z) = self.key(position);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (dx, dy, dz))))
+            .filter_map(move |(dx, dy, dz)| self.buckets.get(&(x + dx, y + dy, z + dz)))
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(id: u64, x: f32, radius: f32) -> CellInformation<f32> {
+        CellInformation {
+            id,
+            position: Point3::new(x, 0., 0.),
+            radius,
+        }
+    }
+
+    fn candidate_ids(spatial_hash: &SpatialHash<f32>, x: f32) -> Vec<u64> {
+        let mut ids: Vec<u64> = spatial_hash
+            .candidates(&Point3::new(x, 0., 0.))
+            .map(|cell| cell.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn overlapping_cells_are_candidates() {
+        // buckets are 2 wide, 0 and 1.9 are in different buckets but overlap
+        let spatial_hash = SpatialHash::new(vec![cell(0, 0., 1.), cell(1, -1.9, 1.)]);
+        assert_eq!(candidate_ids(&spatial_hash, 0.), vec![0, 1]);
+        assert_eq!(candidate_ids(&spatial_hash, -1.9), vec![0, 1]);
+    }
+
+    #[test]
+    fn cells_two_buckets_away_are_no_candidates() {
+        let spatial_hash = SpatialHash::new(vec![cell(0, 0.5, 1.), cell(1, 4.5, 1.)]);
+        assert_eq!(candidate_ids(&spatial_hash, 0.5), vec![0]);
+        assert_eq!(candidate_ids(&spatial_hash, 4.5), vec![1]);
+    }
+
+    #[test]
+    fn the_biggest_cell_sets_the_bucket_size() {
+        let spatial_hash = SpatialHash::new(vec![cell(0, 0., 0.5), cell(1, 3.9, 2.)]);
+        assert_eq!(candidate_ids(&spatial_hash, 0.), vec![0, 1]);
+    }
+
+    #[test]
+    fn cells_without_radius_still_get_buckets() {
+        let spatial_hash = SpatialHash::new(vec![cell(0, 0., 0.), cell(1, 0.5, 0.)]);
+        assert_eq!(candidate_ids(&spatial_hash, 0.), vec![0, 1]);
+    }
+}
@@ -1,6 +1,11 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    error::Error,
+    f32::consts::PI,
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
-use cgmath::{EuclideanSpace, InnerSpace, Point3, SquareMatrix, Vector3, Vector4};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix3, Point3, Rad, SquareMatrix, Vector3, Vector4};
 use wgpu::{
     util::DeviceExt, Adapter, Backends, Buffer, Device, Instance, InstanceDescriptor,
     InstanceFlags, MemoryHints, Queue, RenderPipeline, Surface, TextureFormat,
@@ -8,24 +13,40 @@ use wgpu::{
 use winit::{dpi::PhysicalPosition, window::Window};
 
 use crate::shared::{
-    cell::{Cell, CellEvent, CellEventType, EventSystem},
-    math::{distance, line_plane_intersection, Line, Line2PlaneClassification, Plane},
+    cell::Cell,
+    math::{ray_sphere_intersection, Line},
 };
 
 use super::{
-    camera::{Camera, CameraController, CameraUniform},
+    camera::{Camera, CameraController, CameraUniform, StandardView},
+    capture::write_png,
+    debug_lines::{scale_bar, scale_bar_length},
+    light::LightUniform,
     vertex::Vertex,
 };
 
+/// A second camera shown in the right half of the window, with its own buffer and bind group.
+struct SecondView {
+    camera: Camera,
+    uniform: CameraUniform,
+    buffer: Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
 pub struct ApplicationState<'window> {
     window: Arc<Window>,
+    /// title of the window without the length of the scale bar
+    title: String,
+    /// shown in the title, kept to only set the title when it changes
+    scale_bar_length: Option<(f32, i32)>,
     adapter: Adapter,
     surface: Surface<'window>,
     device: Device,
     queue: Queue,
     render_pipeline: Option<RenderPipeline>,
+    line_pipeline: Option<RenderPipeline>,
+    debug_lines: Vec<Vertex>,
     cells: Arc<Vec<Cell>>,
-    cell_events: Arc<EventSystem>,
     pub mouse_position: Option<PhysicalPosition<f64>>,
     camera: Camera,
     camera_controller: Arc<Mutex<CameraController>>,
@@ -33,13 +54,18 @@ pub struct ApplicationState<'window> {
     camera_buffer: Buffer,
     camera_bind_group: wgpu::BindGroup,
     camera_bind_group_layout: wgpu::BindGroupLayout,
+    light_buffer: Buffer,
+    light_bind_group: wgpu::BindGroup,
+    light_bind_group_layout: wgpu::BindGroupLayout,
+    /// set while the window is split into two views
+    second_view: Option<SecondView>,
 }
 
 impl<'window> ApplicationState<'window> {
     pub async fn new(
         window: Arc<Window>,
+        title: String,
         cells: Arc<Vec<Cell>>,
-        cell_events: Arc<EventSystem>,
         camera_controller: Arc<Mutex<CameraController>>,
     ) -> Self {
         let instance = create_instance();
@@ -80,16 +106,12 @@ impl<'window> ApplicationState<'window> {
             fovy: 45.0,
             znear: 0.1,
             zfar: 100.0,
+            orthographic: false,
         };
 
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update_view_proj(&camera);
 
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Camera Buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[wgpu::BindGroupLayoutEntry {
@@ -104,24 +126,51 @@ impl<'window> ApplicationState<'window> {
                 }],
                 label: Some("camera_bind_group_layout"),
             });
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &camera_bind_group_layout,
+        let (camera_buffer, camera_bind_group) =
+            create_camera_binding(&device, &camera_bind_group_layout, &camera_uniform);
+
+        // light from above until the simulation sets the sun
+        let light_uniform = LightUniform::new(Vector3::unit_y(), 1.);
+        let light_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("light_bind_group_layout"),
+            });
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&[light_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &light_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: camera_buffer.as_entire_binding(),
+                resource: light_buffer.as_entire_binding(),
             }],
-            label: Some("camera_bind_group"),
+            label: Some("light_bind_group"),
         });
 
         let mut state = ApplicationState {
             window,
+            title,
+            scale_bar_length: None,
             adapter,
             surface,
             device,
             queue,
             render_pipeline: None,
+            line_pipeline: None,
+            debug_lines: Vec::new(),
             cells,
-            cell_events,
             mouse_position: None,
             camera,
             camera_controller,
@@ -129,65 +178,146 @@ impl<'window> ApplicationState<'window> {
             camera_buffer,
             camera_bind_group,
             camera_bind_group_layout,
+            light_buffer,
+            light_bind_group,
+            light_bind_group_layout,
+            second_view: None,
         };
-        let render_pipeline = state.get_render_pipeline();
+        let render_pipeline = state.get_render_pipeline(wgpu::PrimitiveTopology::TriangleList);
         state.render_pipeline = Some(render_pipeline);
+        let line_pipeline = state.get_render_pipeline(wgpu::PrimitiveTopology::LineList);
+        state.line_pipeline = Some(line_pipeline);
         state.resize();
         state
     }
 
+    /// replaces the rendered cells, e.g. after the simulation has been reset
+    pub fn set_cells(&mut self, cells: Arc<Vec<Cell>>) {
+        self.cells = cells;
+    }
+
+    /// sets the lines of the debug overlay, each pair of vertices is drawn as one line
+    pub fn set_debug_lines(&mut self, lines: Vec<Vertex>) {
+        self.debug_lines = lines;
+    }
+
+    /// sets the direction the light comes from and how bright it is, as the day night cycle has them
+    pub fn set_light(&self, direction: Vector3<f32>, intensity: f32) {
+        self.queue.write_buffer(
+            &self.light_buffer,
+            0,
+            bytemuck::cast_slice(&[LightUniform::new(direction, intensity)]),
+        );
+    }
+
+    /// whether the window is split and the screen position is in the right half
+    fn in_second_view(&self, screen_pos: &PhysicalPosition<f64>) -> bool {
+        self.second_view.is_some()
+            && screen_pos.x >= (self.window.as_ref().inner_size().width / 2) as f64
+    }
+
+    /// returns the camera shown at the screen position,
+    /// the left edge and the size of its part of the window
+    fn camera_at(&self, screen_pos: &PhysicalPosition<f64>) -> (&Camera, f64, f32, f32) {
+        let size = self.window.as_ref().inner_size();
+        match &self.second_view {
+            Some(second_view) if self.in_second_view(screen_pos) => {
+                let left = size.width / 2;
+                (
+                    &second_view.camera,
+                    left as f64,
+                    (size.width - left) as f32,
+                    size.height as f32,
+                )
+            }
+            Some(_) => (
+                &self.camera,
+                0.,
+                (size.width / 2) as f32,
+                size.height as f32,
+            ),
+            None => (&self.camera, 0., size.width as f32, size.height as f32),
+        }
+    }
+
+    /// returns the ray from the camera through the screen position into the scene
     pub fn screen_pos_2_select_ray(&self, screen_pos: &PhysicalPosition<f64>) -> Line<f32> {
-        let view_projection_matrix = self.camera.build_view_projection_matrix();
+        let (camera, left, width, height) = self.camera_at(screen_pos);
+        let view_projection_matrix = camera.build_view_projection_matrix();
         let inverted = view_projection_matrix.invert().unwrap();
-        let screen_pos: PhysicalPosition<f32> =
-            PhysicalPosition::new(screen_pos.x as f32, screen_pos.y as f32);
-        let front = Vector4::new(screen_pos.x, screen_pos.y, 0., 1.);
-        let back = Vector4::new(screen_pos.x, screen_pos.y, 1., 1.);
-        let front = (inverted * front).truncate();
-        let back = (inverted * back).truncate();
-        let dir = back - front;
+        // normalized device coordinates go from -1 to 1 with y pointing up
+        let ndc_x = (screen_pos.x - left) as f32 / width * 2. - 1.;
+        let ndc_y = 1. - screen_pos.y as f32 / height * 2.;
+        let front = Point3::from_homogeneous(inverted * Vector4::new(ndc_x, ndc_y, 0., 1.));
+        let back = Point3::from_homogeneous(inverted * Vector4::new(ndc_x, ndc_y, 1., 1.));
         Line {
-            pos: front,
-            dir: dir.normalize(),
+            pos: front.to_vec(),
+            dir: (back - front).normalize(),
         }
     }
 
-    pub fn select_cells(&self, select_ray: Line<f32>) {
-        let view_projection_matrix = self.camera.build_view_projection_matrix();
-        let inverted = view_projection_matrix.invert().unwrap();
+    /// returns the ids of all cells the ray goes through, the closest one first
+    pub fn cells_hit(&self, select_ray: Line<f32>) -> Vec<u64> {
+        let mut hits = vec![];
+        for cell in self.cells.iter() {
+            let renderer = cell.renderer.read().unwrap();
+            if let Some(distance) = ray_sphere_intersection(
+                &select_ray,
+                &renderer.position_clone(),
+                renderer.radius_clone(),
+            ) {
+                hits.push((distance, renderer.cell_id()));
+            }
+        }
+        hits.sort_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+        hits.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// returns the point shown at the screen position that is as far away from the camera
+    /// as the point the camera looks at
+    pub fn screen_pos_2_world_pos(&self, screen_pos: &PhysicalPosition<f64>) -> Point3<f32> {
+        let (camera, left, width, height) = self.camera_at(screen_pos);
+        let view_projection_matrix = camera.build_view_projection_matrix();
+        let target_clip = view_projection_matrix * camera.target.to_homogeneous();
+        let depth = target_clip.z / target_clip.w;
+        // normalized device coordinates go from -1 to 1 with y pointing up
+        let ndc_x = (screen_pos.x - left) as f32 / width * 2. - 1.;
+        let ndc_y = 1. - screen_pos.y as f32 / height * 2.;
+        let world =
+            view_projection_matrix.invert().unwrap() * Vector4::new(ndc_x, ndc_y, depth, 1.);
+        Point3::from_homogeneous(world)
+    }
+
+    /// returns the ids of all cells whose center is shown inside the rectangle between the two screen positions,
+    /// in the view the rectangle has been started in
+    pub fn cells_in_rectangle(
+        &self,
+        corner1: &PhysicalPosition<f64>,
+        corner2: &PhysicalPosition<f64>,
+    ) -> Vec<u64> {
+        let (camera, left, width, height) = self.camera_at(corner1);
+        let view_projection_matrix = camera.build_view_projection_matrix();
+        let (min_x, max_x) = (corner1.x.min(corner2.x), corner1.x.max(corner2.x));
+        let (min_y, max_y) = (corner1.y.min(corner2.y), corner1.y.max(corner2.y));
+        let mut inside = vec![];
         for cell in self.cells.iter() {
             let renderer = cell.renderer.read().unwrap();
             let cell_pos = renderer.position();
-            let cell_pos = Vector4::new(cell_pos.x, cell_pos.y, cell_pos.z, 1.);
-            // clip position
-            let cell_pos = (view_projection_matrix * cell_pos).truncate();
-            let cell_plane = Plane::<f32> {
-                pos: cell_pos,
-                normal: select_ray.dir,
-            };
-            match line_plane_intersection(&select_ray, &cell_plane) {
-                Line2PlaneClassification::Parallel => {
-                    panic!("This should not happen!")
-                }
-                Line2PlaneClassification::Intersects(intersection_point) => {
-                    println!(
-                        "ray direction {:?}, intersection point {:?}, cell position {:?}",
-                        select_ray.dir, intersection_point, cell_pos
-                    );
-                    if distance(
-                        &(Point3::origin() + cell_pos),
-                        &(Point3::origin() + intersection_point),
-                    ) < *renderer.radius()
-                    {
-                        println!("Intersection with cell {}", renderer.cell_id());
-                        self.cell_events.notify(Arc::new(CellEvent {
-                            id: renderer.cell_id(),
-                            event_type: CellEventType::Mark(Option::None),
-                        }));
-                    }
-                }
+            let clip_pos =
+                view_projection_matrix * Vector4::new(cell_pos.x, cell_pos.y, cell_pos.z, 1.);
+            // behind the camera
+            if clip_pos.w <= 0. {
+                continue;
+            }
+            // normalized device coordinates go from -1 to 1 with y pointing up
+            let screen_x = (clip_pos.x / clip_pos.w + 1.) / 2. * width;
+            let screen_y = (1. - clip_pos.y / clip_pos.w) / 2. * height;
+            let (screen_x, screen_y) = (screen_x as f64 + left, screen_y as f64);
+            if (min_x..=max_x).contains(&screen_x) && (min_y..=max_y).contains(&screen_y) {
+                inside.push(renderer.cell_id());
             }
         }
+        inside
     }
 
     pub fn render(&self) -> Result<(), wgpu::SurfaceError> {
@@ -246,72 +376,330 @@ impl<'window> ApplicationState<'window> {
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
-            for cell in cells.iter() {
-                let renderer = cell.renderer.read().unwrap();
-                let vertices = renderer.vertices();
-                let indices = renderer.indices();
-                let vertex_buffer =
-                    self.device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Vertex Buffer"),
-                            contents: bytemuck::cast_slice(vertices),
-                            usage: wgpu::BufferUsages::VERTEX,
-                        });
-                let index_buffer =
-                    self.device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Index Buffer"),
-                            contents: bytemuck::cast_slice(indices),
-                            usage: wgpu::BufferUsages::INDEX,
-                        });
-                let num_indices = indices.len() as u32;
-                render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-                render_pass.set_pipeline(&self.render_pipeline.as_ref().unwrap());
-                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                render_pass.draw_indexed(0..num_indices, 0, 0..1);
+            let cell_buffers: Vec<(Buffer, Buffer, u32)> = cells
+                .iter()
+                .map(|cell| {
+                    let renderer = cell.renderer.read().unwrap();
+                    let vertices = renderer.vertices();
+                    let indices = renderer.indices();
+                    let vertex_buffer =
+                        self.device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some("Vertex Buffer"),
+                                contents: bytemuck::cast_slice(vertices),
+                                usage: wgpu::BufferUsages::VERTEX,
+                            });
+                    let index_buffer =
+                        self.device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some("Index Buffer"),
+                                contents: bytemuck::cast_slice(indices),
+                                usage: wgpu::BufferUsages::INDEX,
+                            });
+                    (vertex_buffer, index_buffer, indices.len() as u32)
+                })
+                .collect();
+            let viewports = self.viewports();
+            // the debug lines with the scale bar of the camera of each view
+            let line_buffers: Vec<Option<(Buffer, u32)>> = viewports
+                .iter()
+                .map(|(_, camera, _)| {
+                    if !first {
+                        return None;
+                    }
+                    let mut lines = self.debug_lines.clone();
+                    lines.append(&mut scale_bar(camera));
+                    let buffer =
+                        self.device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some("Debug Line Buffer"),
+                                contents: bytemuck::cast_slice(&lines),
+                                usage: wgpu::BufferUsages::VERTEX,
+                            });
+                    Some((buffer, lines.len() as u32))
+                })
+                .collect();
+            // the same cells are drawn once into every view
+            for ((bind_group, _, [x, y, width, height]), line_buffer) in
+                viewports.into_iter().zip(line_buffers.iter())
+            {
+                render_pass.set_viewport(x, y, width, height, 0., 1.);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.set_bind_group(1, &self.light_bind_group, &[]);
+                render_pass.set_pipeline(self.render_pipeline.as_ref().unwrap());
+                for (vertex_buffer, index_buffer, num_indices) in cell_buffers.iter() {
+                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                    render_pass.draw_indexed(0..*num_indices, 0, 0..1);
+                }
+                if let Some((line_buffer, num_vertices)) = line_buffer {
+                    render_pass.set_pipeline(self.line_pipeline.as_ref().unwrap());
+                    render_pass.set_vertex_buffer(0, line_buffer.slice(..));
+                    render_pass.draw(0..*num_vertices, 0..1);
+                }
             }
         };
         encoder
     }
 
+    /// returns the bind group of each camera and the camera with the part of the window it is shown in
+    fn viewports(&self) -> Vec<(&wgpu::BindGroup, &Camera, [f32; 4])> {
+        let size = self.window.as_ref().inner_size();
+        let (width, height) = (size.width as f32, size.height as f32);
+        match &self.second_view {
+            Some(second_view) => {
+                let left = (size.width / 2) as f32;
+                vec![
+                    (
+                        &self.camera_bind_group,
+                        &self.camera,
+                        [0., 0., left, height],
+                    ),
+                    (
+                        &second_view.bind_group,
+                        &second_view.camera,
+                        [left, 0., width - left, height],
+                    ),
+                ]
+            }
+            None => vec![(
+                &self.camera_bind_group,
+                &self.camera,
+                [0., 0., width, height],
+            )],
+        }
+    }
+
+    /// width divided by height of the part of the window a camera is shown in
+    fn view_aspect(&self) -> f32 {
+        let size = self.window.as_ref().inner_size();
+        let width = match self.second_view {
+            Some(_) => size.width / 2,
+            None => size.width,
+        };
+        width as f32 / size.height as f32
+    }
+
+    /// fits the surface to the window, the cameras keep where they look from and only get the new aspect
     pub fn resize(&mut self) {
         let size = self.window.as_ref().inner_size();
+        if size.width == 0 || size.height == 0 {
+            // minimized, there is nothing to draw into
+            return;
+        }
         let config =
             Surface::get_default_config(&self.surface, &self.adapter, size.width, size.height)
                 .expect("Could not get default configuration for the surface.");
         self.surface.configure(&self.device, &config);
-        let camera = Camera {
-            // position the camera 0 unit up and 3 units back
-            // +z is out of the screen
-            eye: (0.0, 0.0, 3.0).into(),
-            // have it look at the origin
-            target: (0.0, 0.0, 0.0).into(),
-            // which way is "up"
-            up: cgmath::Vector3::unit_y(),
-            aspect: size.width as f32 / size.height as f32,
-            fovy: 45.0,
-            znear: 0.1,
-            zfar: 100.0,
-        };
-        self.camera = camera;
+        let aspect = self.view_aspect();
+        self.camera.aspect = aspect;
+        if let Some(second_view) = self.second_view.as_mut() {
+            second_view.camera.aspect = aspect;
+        }
+        self.write_cameras();
     }
 
+    /// moves the camera under the mouse with the camera controller
     pub fn update_camera(&mut self) {
-        self.camera_controller
+        let controller = Arc::clone(&self.camera_controller);
+        controller
             .lock()
             .as_ref()
             .unwrap()
-            .update_camera(&mut self.camera);
+            .update_camera(self.active_camera());
+        self.write_cameras();
+    }
+
+    /// the camera of the view the mouse is in
+    fn active_camera(&mut self) -> &mut Camera {
+        let in_second_view = self
+            .mouse_position
+            .is_some_and(|position| self.in_second_view(&position));
+        match self.second_view.as_mut() {
+            Some(second_view) if in_second_view => &mut second_view.camera,
+            _ => &mut self.camera,
+        }
+    }
+
+    /// uploads the view projections of all cameras
+    fn write_cameras(&mut self) {
         self.camera_uniform.update_view_proj(&self.camera);
         self.queue.write_buffer(
             &self.camera_buffer,
             0,
             bytemuck::cast_slice(&[self.camera_uniform]),
         );
+        if let Some(second_view) = self.second_view.as_mut() {
+            second_view.uniform.update_view_proj(&second_view.camera);
+            self.queue.write_buffer(
+                &second_view.buffer,
+                0,
+                bytemuck::cast_slice(&[second_view.uniform]),
+            );
+        }
+        self.show_scale_bar_length();
     }
 
-    fn get_render_pipeline(&self) -> RenderPipeline {
+    /// writes the length of the scale bar of the main camera into the title of the window
+    fn show_scale_bar_length(&mut self) {
+        let length = scale_bar_length(&self.camera);
+        if length == self.scale_bar_length {
+            return;
+        }
+        self.scale_bar_length = length;
+        let title = match length {
+            Some((length, exponent)) => format!(
+                "{} - scale bar {:.*}",
+                self.title,
+                (-exponent).max(0) as usize,
+                length
+            ),
+            None => self.title.clone(),
+        };
+        self.window.set_title(&title);
+    }
+
+    /// splits the window into a view with the current camera and a second one looking from the top,
+    /// or goes back to a single view. Returns whether the window is split now.
+    pub fn toggle_split_screen(&mut self) -> bool {
+        self.second_view = match self.second_view {
+            Some(_) => None,
+            None => {
+                let mut camera = self.camera;
+                camera.look_from(StandardView::Top);
+                let mut uniform = CameraUniform::new();
+                uniform.update_view_proj(&camera);
+                let (buffer, bind_group) =
+                    create_camera_binding(&self.device, &self.camera_bind_group_layout, &uniform);
+                Some(SecondView {
+                    camera,
+                    uniform,
+                    buffer,
+                    bind_group,
+                })
+            }
+        };
+        let aspect = self.view_aspect();
+        self.camera.aspect = aspect;
+        if let Some(second_view) = self.second_view.as_mut() {
+            second_view.camera.aspect = aspect;
+        }
+        self.write_cameras();
+        self.second_view.is_some()
+    }
+
+    /// switches between perspective and orthographic projection of the camera under the mouse,
+    /// returns whether it is orthographic now
+    pub fn toggle_orthographic(&mut self) -> bool {
+        let camera = self.active_camera();
+        camera.orthographic = !camera.orthographic;
+        let orthographic = camera.orthographic;
+        self.update_camera();
+        orthographic
+    }
+
+    /// looks at the target of the camera under the mouse from one of the standard directions
+    pub fn look_from(&mut self, view: StandardView) {
+        self.active_camera().look_from(view);
+        self.update_camera();
+    }
+
+    /// moves the cameras so that they look at the target from the same direction and distance as before
+    pub fn focus(&mut self, target: Point3<f32>) {
+        let cameras = std::iter::once(&mut self.camera).chain(
+            self.second_view
+                .as_mut()
+                .map(|second_view| &mut second_view.camera),
+        );
+        for camera in cameras {
+            let offset = target - camera.target;
+            camera.eye += offset;
+            camera.target = target;
+        }
+        self.write_cameras();
+    }
+
+    /// renders the cells into a texture of the window size and writes it as png image
+    pub fn screenshot(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let size = self.window.as_ref().inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Err("The window has no size, it is probably minimized".into());
+        }
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Screenshot Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.encode_cells(&view, &self.cells, true);
+
+        // rows of the buffer have to be aligned
+        let unpadded_bytes_per_row = size.width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Screenshot Buffer"),
+            size: (bytes_per_row * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            extent,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+        let mut rgba = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(bytes_per_row as usize) {
+                for bgra in row[..unpadded_bytes_per_row as usize].chunks(4) {
+                    rgba.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+                }
+            }
+        }
+        buffer.unmap();
+        write_png(path, size.width, size.height, &rgba)
+    }
+
+    /// orbits the camera once around its target and writes a screenshot for every step
+    pub fn record_turntable(
+        &mut self,
+        directory: &Path,
+        frames: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        let rotation = Matrix3::from_axis_angle(self.camera.up, Rad(2. * PI / frames as f32));
+        for frame in 0..frames {
+            self.screenshot(&directory.join(format!("frame_{:04}.png", frame)))?;
+            self.camera.eye =
+                self.camera.target + rotation * (self.camera.eye - self.camera.target);
+            self.write_cameras();
+        }
+        Ok(())
+    }
+
+    fn get_render_pipeline(&self, topology: wgpu::PrimitiveTopology) -> RenderPipeline {
         // Create the shader modules
         let shader = self
             .device
@@ -324,7 +712,10 @@ impl<'window> ApplicationState<'window> {
             self.device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some("Render Pipeline Layout"),
-                    bind_group_layouts: &[&self.camera_bind_group_layout],
+                    bind_group_layouts: &[
+                        &self.camera_bind_group_layout,
+                        &self.light_bind_group_layout,
+                    ],
                     push_constant_ranges: &[],
                 });
 
@@ -341,7 +732,11 @@ impl<'window> ApplicationState<'window> {
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
-                    entry_point: "fs_main", // fragment function name entry point from shader.wgsl
+                    // fragment function name entry point from shader.wgsl, only faces are lit
+                    entry_point: match topology {
+                        wgpu::PrimitiveTopology::TriangleList => "fs_main",
+                        _ => "fs_line",
+                    },
                     targets: &[Some(wgpu::ColorTargetState {
                         format: TextureFormat::Bgra8UnormSrgb,
                         blend: Some(wgpu::BlendState {
@@ -361,10 +756,14 @@ impl<'window> ApplicationState<'window> {
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                 }),
                 primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    topology,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
+                    // lines have no faces that could be culled
+                    cull_mode: match topology {
+                        wgpu::PrimitiveTopology::TriangleList => Some(wgpu::Face::Back),
+                        _ => None,
+                    },
                     ..Default::default()
                 },
                 depth_stencil: None,
@@ -377,6 +776,28 @@ impl<'window> ApplicationState<'window> {
     }
 }
 
+/// creates the uniform buffer a camera is uploaded to and the bind group the shaders read it from
+fn create_camera_binding(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform: &CameraUniform,
+) -> (Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[*uniform]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some("camera_bind_group"),
+    });
+    (buffer, bind_group)
+}
+
 fn create_instance() -> Instance {
     let instance_descriptor = InstanceDescriptor {
         backends: Backends::VULKAN,
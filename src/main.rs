@@ -1,8 +1,8 @@
-use std::{sync::Arc, thread, time::Duration};
+use std::{path::Path, thread, time::Duration};
 
-use cgmath::Point3;
-use engine::Simulation;
-use shared::cell::{Cell, EventSystem};
+use engine::{data_logger::DataLogger, replay::Replay, Simulation};
+use model::{rsml::read_root_cells, scenario::Scenario, settings::SimulationSettings};
+use shared::rng::SimulationRng;
 use winit::event_loop::{ControlFlow, EventLoop};
 
 mod engine;
@@ -14,74 +14,47 @@ enum SimulationEvent {
 }
 
 fn main() {
-    let events = Arc::new(EventSystem::new());
-    let cells = vec![
-        Cell::new(
-            Point3 {
-                x: 0.,
-                y: 0.,
-                z: 0.,
-            },
-            1.,
-            Arc::clone(&events),
+    let rng = match arg_value("--seed") {
+        Some(seed) => SimulationRng::new(
+            seed.parse()
+                .expect("The seed has to be a positive integer!"),
         ),
-        Cell::new(
-            Point3 {
-                x: -1.,
-                y: 0.,
-                z: 0.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-        Cell::new(
-            Point3 {
-                x: 1.,
-                y: 0.,
-                z: 0.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-        Cell::new(
-            Point3 {
-                x: 0.,
-                y: -1.,
-                z: 0.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-        Cell::new(
-            Point3 {
-                x: 0.,
-                y: 1.,
-                z: 0.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-        Cell::new(
-            Point3 {
-                x: 0.,
-                y: 0.,
-                z: -1.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-        Cell::new(
-            Point3 {
-                x: 0.,
-                y: 0.,
-                z: 1.,
-            },
-            1.,
-            Arc::clone(&events),
-        ),
-    ];
+        None => SimulationRng::from_entropy(),
+    };
+    println!("Seed: {}", rng.seed());
 
-    let mut simulation = Simulation::new(cells, events);
+    let csv = arg_value("--csv");
+    let cells_csv = arg_value("--cells-csv");
+    let data_logger = DataLogger::new(
+        csv.as_deref().map(Path::new),
+        cells_csv.as_deref().map(Path::new),
+    )
+    .expect("Could not create the csv files!");
+
+    let mut scenario = match arg_value("--scenario") {
+        Some(path) => Scenario::read(Path::new(&path)).expect("Could not read the scenario file!"),
+        None => Scenario::default(),
+    };
+    if let Some(path) = arg_value("--rsml") {
+        let cells = read_root_cells(Path::new(&path)).expect("Could not read the RSML file!");
+        println!("Seeded {} cells along the roots", cells.len());
+        scenario.cells.extend(cells);
+    }
+
+    let settings = match arg_value("--settings") {
+        Some(path) => {
+            SimulationSettings::read(Path::new(&path)).expect("Could not read the settings file!")
+        }
+        None => SimulationSettings::default(),
+    };
+    let update_interval = Duration::from_millis(settings.update_interval);
+
+    let mut simulation = Simulation::new(scenario, settings, rng, data_logger);
+    if let Some(path) = arg_value("--replay") {
+        let replay = Replay::read(Path::new(&path)).expect("Could not read the recording!");
+        println!("Replaying {} updates", replay.len());
+        simulation.start_replay(replay);
+    }
 
     let event_loop = EventLoop::with_user_event()
         .build()
@@ -91,7 +64,7 @@ fn main() {
 
     thread::spawn(move || loop {
         let _ = proxy.send_event(SimulationEvent::Update);
-        thread::sleep(Duration::from_millis(200));
+        thread::sleep(update_interval);
     });
 
     // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
@@ -105,3 +78,17 @@ fn main() {
 
     event_loop.run_app(&mut simulation).unwrap();
 }
+
+/// Reads the value given after the flag as command line argument.
+/// `--seed <number>` sets the seed of the random numbers,
+/// `--scenario <path>` reads the cells to start with from a RON file like the ones in `scenarios/`,
+/// `--rsml <path>` adds chains of cells along the roots of a root system architecture file,
+/// `--settings <path>` reads the parameters of the simulation from a RON file,
+/// `--csv <path>` writes values of all cells summed up per update to a csv file,
+/// `--cells-csv <path>` writes a row per cell and update to a csv file,
+/// `--replay <path>` plays back such a file instead of simulating.
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.get(index + 1).cloned()
+}
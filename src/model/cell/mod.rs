@@ -1,4 +1,6 @@
 use cgmath::{InnerSpace, Point3, Vector3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     f32::consts::E,
@@ -6,43 +8,131 @@ use std::{
 };
 
 use crate::{
-    engine::cell_renderer::radius_from_volume,
-    model::entity::{generate_id, Entity},
+    model::{
+        day_night_cycle::DayNightCycle,
+        entity::{generate_id, Entity},
+        settings::{MaturationSettings, SimulationSettings},
+    },
     shared::{
         cell::{CellEvent, CellEventType, CellInformation, EventSystem},
-        math::{distance, mean},
+        math::{closest_point_on_segment, distance, radius_from_volume, sphere_overlap_volume},
+        rng::SimulationRng,
     },
 };
 
-pub const SIZE_THRESHOLD: f32 = 20.;
+/// How the volume of a cell develops with its age.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GrowthLaw {
+    /// slow start, fastest at half the size threshold and slowing down towards it
+    #[default]
+    Logistic,
+    /// keeps accelerating until the size threshold is reached
+    Exponential,
+    /// like logistic but fastest earlier, at about a third of the size threshold
+    Gompertz,
+    /// the same amount every update until the size threshold is reached
+    Linear,
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct GrowthFactors {
+    law: GrowthLaw,
     size_threshold: f32,
     growth_factor: f32,
     start_value: f32,
+    contact_inhibition: f32,
+}
+
+impl GrowthFactors {
+    /// randomly scales the size threshold and the growth factor by up to +-variation
+    fn vary(self, variation: f32, rng: &mut impl Rng) -> Self {
+        if variation <= 0. {
+            return self;
+        }
+        let mut vary = |value: f32| value * (1. + rng.gen_range(-variation..=variation));
+        Self {
+            law: self.law,
+            size_threshold: vary(self.size_threshold),
+            growth_factor: vary(self.growth_factor),
+            start_value: self.start_value,
+            contact_inhibition: self.contact_inhibition,
+        }
+    }
+}
+
+/// Phases of the life of a cell, determined by its age.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaturationStage {
+    Young,
+    Expanding,
+    Mature,
+    Senescent,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CarbonFactors {
+    /// sugar produced per update by a cell that is not shaded by its neighbours at noon
+    production_rate: f32,
+    /// sugar consumed per unit of volume the cell grows
+    growth_cost: f32,
+    /// sugar the cell starts with
+    start_value: f32,
+}
+
+/// Everything that changes while a cell is simulated.
+#[derive(Clone, Copy, Debug)]
+pub struct CellSnapshot {
+    pub time_lived: u32,
+    pub age: u32,
+    pub carbon: f32,
+    pub position: Point3<f32>,
+    pub volume: f32,
 }
 
 #[derive(Debug)]
 pub struct BiologicalCell {
     id: u64,
+    /// number of updates the cell has grown, the position on the growth curve
     time_lived: AtomicU32,
+    /// number of updates since the cell was created, whether it grew or not
+    age: AtomicU32,
     growth_factors: GrowthFactors,
+    carbon_factors: CarbonFactors,
+    maturation: MaturationSettings,
+    carbon: RwLock<f32>,
     position: Arc<RwLock<Point3<f32>>>,
     volume: Arc<RwLock<f32>>,
     events: Arc<EventSystem>,
 }
 
 impl BiologicalCell {
-    pub fn new(position: &Point3<f32>, volume: f32, events: Arc<EventSystem>) -> Self {
+    pub fn new(
+        position: &Point3<f32>,
+        volume: f32,
+        events: Arc<EventSystem>,
+        settings: &SimulationSettings,
+        rng: &mut SimulationRng,
+    ) -> Self {
+        let carbon_factors = CarbonFactors {
+            production_rate: settings.carbon_production_rate,
+            growth_cost: settings.carbon_growth_cost,
+            start_value: settings.carbon_start_value,
+        };
         let cell = BiologicalCell {
             id: generate_id(),
             time_lived: AtomicU32::new(0),
+            age: AtomicU32::new(0),
             growth_factors: GrowthFactors {
-                size_threshold: SIZE_THRESHOLD,
-                growth_factor: 0.0005,
+                law: settings.growth_law,
+                size_threshold: settings.size_threshold,
+                growth_factor: settings.growth_factor,
                 start_value: volume,
-            },
+                contact_inhibition: settings.contact_inhibition,
+            }
+            .vary(settings.growth_variation, rng),
+            carbon_factors,
+            maturation: settings.maturation,
+            carbon: RwLock::new(carbon_factors.start_value),
             position: Arc::new(RwLock::new(position.clone())),
             volume: Arc::new(RwLock::new(volume)),
             events,
@@ -67,6 +157,47 @@ impl BiologicalCell {
             .expect("Failed to get position from cell!")
     }
 
+    /// number of updates this cell has grown
+    pub fn time_lived(&self) -> u32 {
+        self.time_lived.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// number of updates since this cell was created
+    pub fn age(&self) -> u32 {
+        self.age.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn carbon(&self) -> f32 {
+        *self.carbon.read().expect("Failed to get carbon from cell!")
+    }
+
+    pub fn snapshot(&self) -> CellSnapshot {
+        CellSnapshot {
+            time_lived: self.time_lived(),
+            age: self.age(),
+            carbon: self.carbon(),
+            position: self.position_clone(),
+            volume: *self.volume(),
+        }
+    }
+
+    /// sets the cell back to the state of the snapshot
+    pub fn restore(&self, snapshot: &CellSnapshot) {
+        self.time_lived
+            .store(snapshot.time_lived, std::sync::atomic::Ordering::Relaxed);
+        self.age
+            .store(snapshot.age, std::sync::atomic::Ordering::Relaxed);
+        *self.carbon.write().unwrap() = snapshot.carbon;
+        self.events.notify(Arc::new(CellEvent {
+            id: self.entity_id(),
+            event_type: CellEventType::UpdatePosition(snapshot.position),
+        }));
+        self.events.notify(Arc::new(CellEvent {
+            id: self.entity_id(),
+            event_type: CellEventType::UpdateVolume(snapshot.volume),
+        }));
+    }
+
     fn handle_events(&self) {
         let pos = Arc::clone(&self.position);
         let volume = Arc::clone(&self.volume);
@@ -88,66 +219,129 @@ impl BiologicalCell {
         });
     }
 
-    pub fn update(&self, near_cells: &HashMap<u64, CellInformation<f32>>) {
+    pub fn update(
+        &self,
+        near_cells: &HashMap<u64, CellInformation<f32>>,
+        day_night_cycle: &DayNightCycle,
+    ) {
+        self.age.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.photosynthesize(near_cells, day_night_cycle);
+        self.grow(near_cells);
+        // println!("Cell {} at {:?}", self.entity_id(), self.position());
+    }
+
+    /// produce sugar depending on the daylight if no near cell is shading this cell
+    fn photosynthesize(
+        &self,
+        near_cells: &HashMap<u64, CellInformation<f32>>,
+        day_night_cycle: &DayNightCycle,
+    ) {
+        let light_intensity = day_night_cycle.light_intensity();
+        if light_intensity <= 0. || self.is_shaded(near_cells, day_night_cycle.light_direction()) {
+            return;
+        }
+        let mut carbon = self.carbon.write().unwrap();
+        *carbon += self.carbon_factors.production_rate * light_intensity;
+    }
+
+    /// a cell is shaded if another cell lies between it and the light
+    fn is_shaded(
+        &self,
+        near_cells: &HashMap<u64, CellInformation<f32>>,
+        light_direction: Vector3<f32>,
+    ) -> bool {
+        let position = self.position_clone();
+        near_cells.values().any(|other| {
+            let to_other = other.position - position;
+            if to_other.dot(light_direction) <= 0. {
+                return false;
+            }
+            let ray_end = position + light_direction * to_other.magnitude();
+            let closest = closest_point_on_segment(&other.position, &position, &ray_end);
+            distance(&closest, &other.position) < other.radius
+        })
+    }
+
+    /// grow one step if there is enough sugar for it, a starved cell stops growing.
+    /// The more volume the cell shares with its neighbours, the less it grows.
+    fn grow(&self, near_cells: &HashMap<u64, CellInformation<f32>>) {
+        let time_lived = self.time_lived.load(std::sync::atomic::Ordering::Relaxed);
+        let growth = growth_curve(self.growth_factors);
+        let increase = (growth(time_lived + 1) - growth(time_lived))
+            * self.inhibition(near_cells)
+            * self.stage_growth();
+        // a shrinking cell does not get its sugar back
+        let cost = increase.max(0.) * self.carbon_factors.growth_cost;
+        {
+            let mut carbon = self.carbon.write().unwrap();
+            if *carbon < cost {
+                return;
+            }
+            *carbon -= cost;
+        }
         self.time_lived
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // the cell owns its volume, the event only tells the renderer about the new size
+        let new_volume = {
+            let mut volume = self.volume.write().unwrap();
+            *volume += increase;
+            *volume
+        };
 
-        let new_volume = logistic_growth(self.growth_factors)(
-            self.time_lived.load(std::sync::atomic::Ordering::Relaxed),
-        );
         let event = CellEvent {
             id: self.entity_id(),
             event_type: CellEventType::UpdateVolume(new_volume),
         };
         self.events.notify(Arc::new(event));
-
-        self.reposition(near_cells);
-        // println!("Cell {} at {:?}", self.entity_id(), self.position());
     }
 
-    /// move self away from near cells
-    fn reposition(&self, near_cells: &HashMap<u64, CellInformation<f32>>) {
-        let mut positions = vec![];
-        near_cells
-            .values()
-            .filter(|other| {
-                distance(&self.position_clone(), &other.position)
-                    < f32::max(radius_from_volume(&self.volume()), other.radius)
-            })
-            .for_each(|near| {
-                positions.push(self.get_point_away_from(near));
-            });
-        if positions.len() > 0 {
-            let event = CellEvent {
-                id: self.entity_id(),
-                event_type: CellEventType::UpdatePosition(mean(&positions)),
-            };
-            self.events.notify(Arc::new(event));
+    pub fn stage(&self) -> MaturationStage {
+        let age = self.age();
+        if age >= self.maturation.senescent_age {
+            MaturationStage::Senescent
+        } else if age >= self.maturation.mature_age {
+            MaturationStage::Mature
+        } else if age >= self.maturation.expanding_age {
+            MaturationStage::Expanding
+        } else {
+            MaturationStage::Young
         }
     }
 
-    /// finds a point away from the other cell and returns it
-    fn get_point_away_from(&self, from: &CellInformation<f32>) -> Point3<f32> {
-        let p1 = &self.position_clone();
-        let p2 = &from.position;
-        let r1 = radius_from_volume(&self.volume());
-        let r2 = from.radius;
-
-        let direction = Vector3::<f32> {
-            x: p1.x - p2.x,
-            y: p1.y - p2.y,
-            z: p1.z - p2.z,
+    /// growth multiplier of the current maturation stage
+    fn stage_growth(&self) -> f32 {
+        match self.stage() {
+            MaturationStage::Young => self.maturation.young_growth,
+            MaturationStage::Expanding => self.maturation.expanding_growth,
+            MaturationStage::Mature => self.maturation.mature_growth,
+            MaturationStage::Senescent => self.maturation.senescent_growth,
         }
-        .normalize();
-
-        let current_dist = distance(p1, p2);
-        let to_dist = f32::max(r1, r2);
-        let dist = to_dist - current_dist;
-        Point3::<f32> {
-            x: p1.x + direction.x * dist,
-            y: p1.y + direction.y * dist,
-            z: p1.z + direction.z * dist,
+    }
+
+    /// factor between 1 for a free cell and 0 for a cell too crowded to grow
+    fn inhibition(&self, near_cells: &HashMap<u64, CellInformation<f32>>) -> f32 {
+        let contact_inhibition = self.growth_factors.contact_inhibition;
+        if contact_inhibition <= 0. {
+            return 1.;
         }
+        let position = self.position_clone();
+        let volume = *self.volume.read().unwrap();
+        let radius = radius_from_volume(&volume);
+        // summed by id, the order of the map changes between runs and so would the rounding
+        let mut overlaps: Vec<(u64, f32)> = near_cells
+            .values()
+            .map(|other| {
+                let overlap = sphere_overlap_volume(
+                    radius,
+                    other.radius,
+                    distance(&position, &other.position),
+                );
+                (other.id, overlap)
+            })
+            .collect();
+        overlaps.sort_by_key(|(id, _)| *id);
+        let overlap: f32 = overlaps.iter().map(|(_, overlap)| overlap).sum();
+        (1. - contact_inhibition * overlap / volume).max(0.)
     }
 }
 
@@ -157,6 +351,16 @@ impl Entity for BiologicalCell {
     }
 }
 
+/// returns the volume depending on the age following the growth law
+fn growth_curve(growth_factors: GrowthFactors) -> Box<dyn Fn(u32) -> f32> {
+    match growth_factors.law {
+        GrowthLaw::Logistic => Box::new(logistic_growth(growth_factors)),
+        GrowthLaw::Exponential => Box::new(exponential_growth(growth_factors)),
+        GrowthLaw::Gompertz => Box::new(gompertz_growth(growth_factors)),
+        GrowthLaw::Linear => Box::new(linear_growth(growth_factors)),
+    }
+}
+
 fn logistic_growth(growth_factors: GrowthFactors) -> impl Fn(u32) -> f32 {
     // f'(t)=k*f(t)*(G-f(t))
     // => f(t)=1/(1+e^(-k*G*t)*(G/f(0)-1))
@@ -169,3 +373,199 @@ fn logistic_growth(growth_factors: GrowthFactors) -> impl Fn(u32) -> f32 {
                 ) * (growth_factors.size_threshold / growth_factors.start_value - 1.))
     };
 }
+
+fn exponential_growth(growth_factors: GrowthFactors) -> impl Fn(u32) -> f32 {
+    // f'(t)=k*G*f(t) until f(t)=G
+    // => f(t)=min(f(0)*e^(k*G*t), G)
+    // a cell that starts above G keeps its volume instead of being cut down to G
+    let limit = f32::max(growth_factors.size_threshold, growth_factors.start_value);
+    move |t: u32| {
+        f32::min(
+            growth_factors.start_value
+                * f32::powf(
+                    E,
+                    growth_factors.growth_factor * growth_factors.size_threshold * t as f32,
+                ),
+            limit,
+        )
+    }
+}
+
+fn gompertz_growth(growth_factors: GrowthFactors) -> impl Fn(u32) -> f32 {
+    // f'(t)=k*G*f(t)*ln(G/f(t))
+    // => f(t)=G*e^(ln(f(0)/G)*e^(-k*G*t))
+    move |t: u32| {
+        growth_factors.size_threshold
+            * f32::powf(
+                E,
+                f32::ln(growth_factors.start_value / growth_factors.size_threshold)
+                    * f32::powf(
+                        E,
+                        -growth_factors.growth_factor * growth_factors.size_threshold * t as f32,
+                    ),
+            )
+    }
+}
+
+fn linear_growth(growth_factors: GrowthFactors) -> impl Fn(u32) -> f32 {
+    // f'(t)=k*G until f(t)=G
+    // => f(t)=min(f(0)+k*G*t, G)
+    // a cell that starts above G keeps its volume instead of being cut down to G
+    let limit = f32::max(growth_factors.size_threshold, growth_factors.start_value);
+    move |t: u32| {
+        f32::min(
+            growth_factors.start_value
+                + growth_factors.growth_factor * growth_factors.size_threshold * t as f32,
+            limit,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(volume: f32) -> BiologicalCell {
+        BiologicalCell::new(
+            &Point3::new(0., 0., 0.),
+            volume,
+            Arc::new(EventSystem::new()),
+            &SimulationSettings::default(),
+            &mut SimulationRng::new(1),
+        )
+    }
+
+    fn growth_factors(law: GrowthLaw, start_value: f32) -> GrowthFactors {
+        GrowthFactors {
+            law,
+            size_threshold: 20.,
+            growth_factor: 0.001,
+            start_value,
+            contact_inhibition: 0.,
+        }
+    }
+
+    /// compares the growth curve of the law with its closed form at some ages
+    fn assert_follows(law: GrowthLaw, closed_form: impl Fn(f32) -> f32) {
+        let curve = growth_curve(growth_factors(law, 1.));
+        for t in [0, 1, 10, 50, 100, 500, 1000] {
+            let expected = closed_form(t as f32);
+            assert!(
+                (curve(t) - expected).abs() < 1e-3 * expected,
+                "{:?} at {}: {} instead of {}",
+                law,
+                t,
+                curve(t),
+                expected
+            );
+        }
+    }
+
+    // f(0) = 1, G = 20, k = 0.001, so k * G = 0.02
+
+    #[test]
+    fn logistic_growth_follows_its_closed_form() {
+        assert_follows(GrowthLaw::Logistic, |t| {
+            20. / (1. + (-0.02 * t).exp() * (20. / 1. - 1.))
+        });
+    }
+
+    #[test]
+    fn exponential_growth_follows_its_closed_form() {
+        assert_follows(GrowthLaw::Exponential, |t| (0.02 * t).exp().min(20.));
+    }
+
+    #[test]
+    fn gompertz_growth_follows_its_closed_form() {
+        assert_follows(GrowthLaw::Gompertz, |t| {
+            20. * ((1f32 / 20.).ln() * (-0.02 * t).exp()).exp()
+        });
+    }
+
+    #[test]
+    fn linear_growth_follows_its_closed_form() {
+        assert_follows(GrowthLaw::Linear, |t| (1. + 0.02 * t).min(20.));
+    }
+
+    #[test]
+    fn every_law_starts_at_the_start_value() {
+        for law in [
+            GrowthLaw::Logistic,
+            GrowthLaw::Exponential,
+            GrowthLaw::Gompertz,
+            GrowthLaw::Linear,
+        ] {
+            for start_value in [1., 30.] {
+                let curve = growth_curve(growth_factors(law, start_value));
+                assert!((curve(0) - start_value).abs() < 1e-4, "{:?}", law);
+            }
+        }
+    }
+
+    #[test]
+    fn clamped_laws_keep_a_start_value_above_the_threshold() {
+        for law in [GrowthLaw::Exponential, GrowthLaw::Linear] {
+            let curve = growth_curve(growth_factors(law, 30.));
+            for t in [0, 1, 100, 10000] {
+                assert_eq!(curve(t), 30., "{:?} at {}", law, t);
+            }
+        }
+    }
+
+    #[test]
+    fn sigmoid_laws_approach_the_threshold_from_above() {
+        for law in [GrowthLaw::Logistic, GrowthLaw::Gompertz] {
+            let curve = growth_curve(growth_factors(law, 30.));
+            let mut last = curve(0);
+            for t in 1..1000 {
+                let value = curve(t);
+                assert!(value <= last && value >= 20., "{:?} at {}", law, t);
+                last = value;
+            }
+            assert!((curve(1000) - 20.).abs() < 0.1, "{:?}", law);
+        }
+    }
+
+    #[test]
+    fn growing_adds_the_step_of_the_curve() {
+        let cell = cell(1.);
+        let settings = SimulationSettings::default();
+        let curve = growth_curve(GrowthFactors {
+            growth_factor: settings.growth_factor,
+            ..growth_factors(settings.growth_law, 1.)
+        });
+        for _ in 0..3 {
+            cell.update(&HashMap::new(), &DayNightCycle::new(300));
+        }
+        assert!((*cell.volume() - curve(3)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn restore_sets_the_values_of_the_snapshot() {
+        let cell = cell(1.);
+        let snapshot = CellSnapshot {
+            time_lived: 3,
+            age: 5,
+            carbon: 0.5,
+            position: Point3::new(1., 2., 3.),
+            volume: 2.,
+        };
+        cell.restore(&snapshot);
+        assert_eq!(cell.position_clone(), Point3::new(1., 2., 3.));
+        assert_eq!(*cell.volume(), 2.);
+        assert_eq!(cell.time_lived(), 3);
+        assert_eq!(cell.age(), 5);
+        assert_eq!(cell.carbon(), 0.5);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_undoes_an_update() {
+        let cell = cell(1.);
+        let snapshot = cell.snapshot();
+        cell.update(&HashMap::new(), &DayNightCycle::new(300));
+        cell.restore(&snapshot);
+        assert_eq!(*cell.volume(), 1.);
+        assert_eq!(cell.age(), 0);
+        assert_eq!(cell.time_lived(), 0);
+    }
+}
@@ -0,0 +1,42 @@
+use cgmath::Vector3;
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DayNightCycle {
+    day_length: u32,
+    time_of_day: u32,
+}
+
+impl DayNightCycle {
+    pub fn new(day_length: u32) -> Self {
+        Self {
+            day_length,
+            time_of_day: 0,
+        }
+    }
+
+    /// moves on by one simulation update, starting a new day when the old one is over
+    pub fn advance(&mut self) {
+        self.time_of_day = (self.time_of_day + 1) % self.day_length;
+    }
+
+    /// 0 at sunrise, PI at sunset and between PI and 2 PI at night
+    fn sun_angle(&self) -> f32 {
+        2. * PI * self.time_of_day as f32 / self.day_length as f32
+    }
+
+    /// direction the light is coming from, the sun rises in +x and sets in -x
+    pub fn light_direction(&self) -> Vector3<f32> {
+        let angle = self.sun_angle();
+        Vector3 {
+            x: angle.cos(),
+            y: angle.sin(),
+            z: 0.,
+        }
+    }
+
+    /// between 0 at night and 1 at noon
+    pub fn light_intensity(&self) -> f32 {
+        f32::max(0., self.sun_angle().sin())
+    }
+}
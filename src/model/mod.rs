@@ -1,2 +1,7 @@
 pub mod cell;
+pub mod day_night_cycle;
 pub mod entity;
+pub mod obstacle;
+pub mod rsml;
+pub mod scenario;
+pub mod settings;
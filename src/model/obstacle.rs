@@ -0,0 +1,127 @@
+use cgmath::{InnerSpace, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Static shape cells cannot move into, like the ground, the walls of a pot or a rock.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Obstacle {
+    /// everything behind the plane is solid, the normal points to the free side
+    Plane {
+        point: (f32, f32, f32),
+        normal: (f32, f32, f32),
+    },
+    /// a solid ball
+    Sphere {
+        center: (f32, f32, f32),
+        radius: f32,
+    },
+}
+
+impl Obstacle {
+    /// a plane needs a direction to push to and a sphere a size
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Obstacle::Plane { normal, .. } => {
+                let length = Vector3::from(normal).magnitude();
+                if !length.is_finite() || length == 0. {
+                    return Err(format!(
+                        "The normal of a plane obstacle needs a length, got {:?}",
+                        normal
+                    ));
+                }
+            }
+            Obstacle::Sphere { radius, .. } => {
+                if !radius.is_finite() || radius < 0. {
+                    return Err(format!(
+                        "The radius of a sphere obstacle cannot be negative, got {}",
+                        radius
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// returns the closest position to the given one at which a cell with the radius does not reach into the obstacle
+    pub fn push_out(&self, position: Point3<f32>, radius: f32) -> Point3<f32> {
+        match *self {
+            Obstacle::Plane { point, normal } => {
+                let normal = Vector3::from(normal).normalize();
+                let distance = (position - Point3::from(point)).dot(normal);
+                if distance < radius {
+                    position + normal * (radius - distance)
+                } else {
+                    position
+                }
+            }
+            Obstacle::Sphere {
+                center,
+                radius: obstacle_radius,
+            } => {
+                let center = Point3::from(center);
+                let offset = position - center;
+                let distance = offset.magnitude();
+                let min_distance = obstacle_radius + radius;
+                if distance >= min_distance {
+                    position
+                } else if distance > 0. {
+                    center + offset / distance * min_distance
+                } else {
+                    center + Vector3::unit_y() * min_distance
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUND: Obstacle = Obstacle::Plane {
+        point: (0., -1., 0.),
+        normal: (0., 2., 0.),
+    };
+    const ROCK: Obstacle = Obstacle::Sphere {
+        center: (0., 0., 0.),
+        radius: 2.,
+    };
+
+    #[test]
+    fn a_plane_pushes_cells_along_its_normal() {
+        let pushed = GROUND.push_out(Point3::new(3., -0.5, 1.), 1.);
+        assert_eq!(pushed, Point3::new(3., 0., 1.));
+        let behind = GROUND.push_out(Point3::new(0., -4., 0.), 1.);
+        assert_eq!(behind, Point3::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn a_plane_keeps_free_cells() {
+        let free = Point3::new(1., 0.5, 0.);
+        assert_eq!(GROUND.push_out(free, 1.), free);
+    }
+
+    #[test]
+    fn a_sphere_pushes_cells_away_from_its_center() {
+        let pushed = ROCK.push_out(Point3::new(0., 0., 1.), 1.);
+        assert_eq!(pushed, Point3::new(0., 0., 3.));
+        let centered = ROCK.push_out(Point3::new(0., 0., 0.), 1.);
+        assert_eq!(centered, Point3::new(0., 3., 0.));
+    }
+
+    #[test]
+    fn a_sphere_keeps_free_cells() {
+        let free = Point3::new(3., 1., 0.);
+        assert_eq!(ROCK.push_out(free, 1.), free);
+    }
+
+    #[test]
+    fn a_plane_without_normal_is_invalid() {
+        let plane = Obstacle::Plane {
+            point: (0., 0., 0.),
+            normal: (0., 0., 0.),
+        };
+        assert!(plane.validate().is_err());
+        assert!(GROUND.validate().is_ok());
+        assert!(ROCK.validate().is_ok());
+    }
+}
@@ -0,0 +1,227 @@
+use std::{error::Error, path::Path};
+
+use cgmath::{InnerSpace, Point3};
+use quick_xml::{events::Event, Reader};
+
+use crate::shared::math::radius_from_volume;
+
+use super::scenario::CellDescription;
+
+type Polyline = Vec<Point3<f32>>;
+
+/// volume of the cells seeded along the roots
+const ROOT_CELL_VOLUME: f32 = 0.3;
+/// distance between neighbouring root cells in multiples of their radius
+const ROOT_CELL_SPACING: f32 = 1.5;
+
+/// Reads the root axes of a root system architecture (RSML) file
+/// and returns cells in a chain along each of them.
+/// Coordinates are divided by the resolution of the file, so they are in its unit,
+/// and the y axis is flipped because RSML uses image coordinates with y pointing down.
+pub fn read_root_cells(path: &Path) -> Result<Vec<CellDescription>, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+    root_cells(&content)
+}
+
+/// returns cells in a chain along each root axis of the RSML content
+fn root_cells(content: &str) -> Result<Vec<CellDescription>, Box<dyn Error>> {
+    let (polylines, resolution) = parse_polylines(content)?;
+    let spacing = ROOT_CELL_SPACING * radius_from_volume(&ROOT_CELL_VOLUME);
+    let cells = polylines
+        .iter()
+        .flat_map(|polyline| {
+            let points: Vec<Point3<f32>> = polyline
+                .iter()
+                .map(|p| Point3::new(p.x / resolution, -p.y / resolution, p.z / resolution))
+                .collect();
+            chain(&points, spacing)
+        })
+        .map(|position| CellDescription {
+            position: (position.x, position.y, position.z),
+            volume: ROOT_CELL_VOLUME,
+        })
+        .collect();
+    Ok(cells)
+}
+
+/// returns the points of every polyline in the file and the resolution given in its metadata or 1.
+/// Points need an x and a y coordinate, a missing z coordinate is 0.
+fn parse_polylines(content: &str) -> Result<(Vec<Polyline>, f32), Box<dyn Error>> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+    let mut polylines = vec![];
+    let mut polyline: Option<Polyline> = None;
+    let mut in_resolution = false;
+    let mut resolution: f32 = 1.;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                b"polyline" => polyline = Some(vec![]),
+                b"resolution" => in_resolution = true,
+                b"point" => {
+                    let coordinate = |name: &str| -> Result<Option<f32>, Box<dyn Error>> {
+                        match element.try_get_attribute(name)? {
+                            Some(value) => Ok(Some(value.unescape_value()?.trim().parse()?)),
+                            None => Ok(None),
+                        }
+                    };
+                    let required = |name: &str| -> Result<f32, Box<dyn Error>> {
+                        coordinate(name)?
+                            .ok_or_else(|| format!("A point has no {} coordinate!", name).into())
+                    };
+                    let point = Point3::new(
+                        required("x")?,
+                        required("y")?,
+                        coordinate("z")?.unwrap_or(0.),
+                    );
+                    if let Some(polyline) = polyline.as_mut() {
+                        polyline.push(point);
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_resolution => {
+                resolution = text.unescape()?.trim().parse()?;
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"polyline" => polylines.extend(polyline.take()),
+                b"resolution" => in_resolution = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !resolution.is_finite() || resolution <= 0. {
+        return Err("The resolution has to be a positive number!".into());
+    }
+    Ok((polylines, resolution))
+}
+
+/// returns points along the polyline that are the spacing apart, starting at its first point
+fn chain(points: &[Point3<f32>], spacing: f32) -> Vec<Point3<f32>> {
+    let Some(first) = points.first() else {
+        return vec![];
+    };
+    let mut chain = vec![*first];
+    // how far along the current segment the next point is
+    let mut next = spacing;
+    for segment in points.windows(2) {
+        let direction = segment[1] - segment[0];
+        let length = direction.magnitude();
+        while next <= length {
+            chain.push(segment[0] + direction * (next / length));
+            next += spacing;
+        }
+        next -= length;
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    const RSML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rsml>
+  <metadata>
+    <resolution>2</resolution>
+  </metadata>
+  <scene>
+    <plant>
+      <root>
+        <geometry>
+          <polyline>
+            <point x="0" y="0"/>
+            <point x="0" y="4" z="2"/>
+          </polyline>
+        </geometry>
+        <root>
+          <geometry>
+            <polyline>
+              <point x="2" y="6"/>
+            </polyline>
+          </geometry>
+        </root>
+      </root>
+    </plant>
+  </scene>
+</rsml>"#;
+
+    fn point(x: f32, y: f32, z: f32) -> Point3<f32> {
+        Point3 { x, y, z }
+    }
+
+    #[test]
+    fn polylines_and_resolution_are_parsed() {
+        let (polylines, resolution) = parse_polylines(RSML).unwrap();
+        assert_eq!(resolution, 2.);
+        assert_eq!(
+            polylines,
+            vec![
+                vec![point(0., 0., 0.), point(0., 4., 2.)],
+                vec![point(2., 6., 0.)]
+            ]
+        );
+    }
+
+    #[test]
+    fn resolution_defaults_to_one() {
+        let content = r#"<rsml><polyline><point x="1" y="2"/></polyline></rsml>"#;
+        let (_, resolution) = parse_polylines(content).unwrap();
+        assert_eq!(resolution, 1.);
+    }
+
+    #[test]
+    fn point_without_x_or_y_is_an_error() {
+        assert!(parse_polylines(r#"<rsml><polyline><point y="2"/></polyline></rsml>"#).is_err());
+        assert!(parse_polylines(r#"<rsml><polyline><point x="1"/></polyline></rsml>"#).is_err());
+    }
+
+    #[test]
+    fn non_positive_resolution_is_an_error() {
+        let content = r#"<rsml><resolution>0</resolution></rsml>"#;
+        assert!(parse_polylines(content).is_err());
+    }
+
+    #[test]
+    fn resolution_that_is_no_number_is_an_error() {
+        for resolution in ["NaN", "inf"] {
+            let content = format!("<rsml><resolution>{}</resolution></rsml>", resolution);
+            assert!(parse_polylines(&content).is_err());
+        }
+    }
+
+    #[test]
+    fn cells_are_scaled_by_resolution_and_flipped() {
+        let cells = root_cells(RSML).unwrap();
+        let last = cells.last().unwrap();
+        assert_eq!(last.position, (1., -3., 0.));
+        assert!(cells
+            .iter()
+            .all(|cell| cell.position.1 <= 0. && cell.volume == ROOT_CELL_VOLUME));
+    }
+
+    #[test]
+    fn chain_carries_spacing_across_segments() {
+        let points = vec![point(0., 0., 0.), point(1.5, 0., 0.), point(1.5, 2., 0.)];
+        let chain = chain(&points, 1.);
+        let expected = [
+            point(0., 0., 0.),
+            point(1., 0., 0.),
+            point(1.5, 0.5, 0.),
+            point(1.5, 1.5, 0.),
+        ];
+        assert_eq!(chain.len(), expected.len());
+        for (actual, expected) in chain.iter().zip(expected) {
+            assert!((actual - expected).magnitude() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn chain_of_nothing_is_empty() {
+        assert!(chain(&[], 1.).is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+use std::{error::Error, path::Path, sync::Arc};
+
+use cgmath::{Point3, Vector3};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    model::{obstacle::Obstacle, settings::SimulationSettings},
+    shared::{
+        cell::{Cell, EventSystem},
+        math::radius_from_volume,
+        rng::SimulationRng,
+    },
+};
+
+/// Directory the bundled scenarios are in and exported scenarios are written to.
+pub const SCENARIO_DIRECTORY: &str = "scenarios";
+/// distance between neighbouring meristem cells in multiples of their radius
+const MERISTEM_CELL_SPACING: f32 = 1.5;
+/// Scenario used when none is given on the command line.
+const DEFAULT_SCENARIO: &str = include_str!("../../scenarios/star.ron");
+
+/// The cells a simulation starts with and the obstacles around them, read from a RON file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub cells: Vec<CellDescription>,
+    /// generates cells in addition to the listed ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meristem: Option<Meristem>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct CellDescription {
+    pub position: (f32, f32, f32),
+    pub volume: f32,
+}
+
+/// A dome of cells packed into the upper half of an ellipsoid, like a shoot apical meristem.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Meristem {
+    /// center of the base of the dome
+    pub center: (f32, f32, f32),
+    /// half the extent of the ellipsoid along x, y and z, the dome is the half above the center
+    pub semi_axes: (f32, f32, f32),
+    pub cell_count: usize,
+    pub cell_volume: f32,
+}
+
+impl Meristem {
+    /// returns the centers of the cells closest to the center of the dome
+    /// on a face-centered cubic lattice, the densest packing of spheres.
+    /// Neighbours are closer than the sum of their radii, so they touch from the start.
+    pub fn cell_positions(&self) -> Result<Vec<Point3<f32>>, String> {
+        self.validate()?;
+        let neighbour_distance = MERISTEM_CELL_SPACING * radius_from_volume(&self.cell_volume);
+        // the points with an even sum of coordinates of a cubic grid form the lattice
+        let step = neighbour_distance / 2f32.sqrt();
+        let (a, b, c) = self.semi_axes;
+        let n = (a.max(b).max(c) / step).ceil() as i32 + 1;
+        let mut positions = vec![];
+        for i in -n..=n {
+            for j in 0..=n {
+                for k in -n..=n {
+                    if (i + j + k) % 2 != 0 {
+                        continue;
+                    }
+                    let (x, y, z) = (i as f32 * step, j as f32 * step, k as f32 * step);
+                    let ellipsoid_distance = (x / a).powi(2) + (y / b).powi(2) + (z / c).powi(2);
+                    if ellipsoid_distance <= 1. {
+                        positions.push((ellipsoid_distance, Vector3 { x, y, z }));
+                    }
+                }
+            }
+        }
+        if positions.len() < self.cell_count {
+            println!(
+                "Only {} of {} cells fit into the meristem!",
+                positions.len(),
+                self.cell_count
+            );
+        }
+        positions.sort_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+        Ok(positions
+            .iter()
+            .take(self.cell_count)
+            .map(|(_, offset)| Point3::from(self.center) + offset)
+            .collect())
+    }
+
+    /// the lattice cannot be built for cells or a dome without extent
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.cell_volume.is_finite() || self.cell_volume <= 0. {
+            return Err(format!(
+                "The cell_volume of the meristem has to be positive, got {}",
+                self.cell_volume
+            ));
+        }
+        let (a, b, c) = self.semi_axes;
+        if [a, b, c]
+            .iter()
+            .any(|axis| !axis.is_finite() || *axis <= 0.)
+        {
+            return Err(format!(
+                "The semi_axes of the meristem have to be positive, got {:?}",
+                self.semi_axes
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let scenario: Self = ron::from_str(&content)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// checks the values serde cannot, like cells without volume
+    pub fn validate(&self) -> Result<(), String> {
+        for cell in self.cells.iter() {
+            let (x, y, z) = cell.position;
+            if ![x, y, z].iter().all(|value| value.is_finite()) {
+                return Err(format!(
+                    "The position of a cell has to be a number, got {:?}",
+                    cell.position
+                ));
+            }
+            if !cell.volume.is_finite() || cell.volume <= 0. {
+                return Err(format!(
+                    "The volume of a cell has to be positive, got {}",
+                    cell.volume
+                ));
+            }
+        }
+        if let Some(meristem) = &self.meristem {
+            meristem.validate()?;
+        }
+        for obstacle in self.obstacles.iter() {
+            obstacle.validate()?;
+        }
+        Ok(())
+    }
+
+    /// the same scenario but with the cells as they are right now,
+    /// the generated meristem cells are listed like all others
+    pub fn with_cells(&self, cells: &[Cell]) -> Self {
+        let cells = cells
+            .iter()
+            .map(|cell| {
+                let bio = cell.bio.read().unwrap();
+                let position = bio.position_clone();
+                let volume = *bio.volume();
+                CellDescription {
+                    position: (position.x, position.y, position.z),
+                    volume,
+                }
+            })
+            .collect();
+        Self {
+            cells,
+            meristem: None,
+            obstacles: self.obstacles.clone(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let content = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// creates the cells described by the scenario
+    pub fn spawn_cells(
+        &self,
+        events: &Arc<EventSystem>,
+        settings: &SimulationSettings,
+        rng: &mut SimulationRng,
+    ) -> Vec<Cell> {
+        let mut cells: Vec<Cell> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let (x, y, z) = cell.position;
+                Cell::new(
+                    Point3 { x, y, z },
+                    cell.volume,
+                    Arc::clone(events),
+                    settings,
+                    rng,
+                )
+            })
+            .collect();
+        if let Some(meristem) = &self.meristem {
+            match meristem.cell_positions() {
+                Ok(positions) => {
+                    for position in positions {
+                        cells.push(Cell::new(
+                            position,
+                            meristem.cell_volume,
+                            Arc::clone(events),
+                            settings,
+                            rng,
+                        ));
+                    }
+                }
+                Err(err) => println!("Could not place the meristem cells! Error: {}", err),
+            }
+        }
+        cells
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        ron::from_str(DEFAULT_SCENARIO).expect("The bundled default scenario is invalid!")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::math::distance;
+
+    fn meristem(semi_axes: (f32, f32, f32), cell_count: usize, cell_volume: f32) -> Meristem {
+        Meristem {
+            center: (1., 2., 3.),
+            semi_axes,
+            cell_count,
+            cell_volume,
+        }
+    }
+
+    fn directory() -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join(format!("scenario-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn a_written_scenario_is_read_back() {
+        let scenario = Scenario {
+            cells: vec![CellDescription {
+                position: (1., -2., 0.5),
+                volume: 3.,
+            }],
+            meristem: Some(meristem((4., 2., 4.), 7, 1.)),
+            obstacles: vec![Obstacle::Sphere {
+                center: (0., -5., 0.),
+                radius: 2.,
+            }],
+        };
+        let path = directory().join("round_trip.ron");
+        scenario.write(&path).unwrap();
+        let read = Scenario::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.cells.len(), 1);
+        assert_eq!(read.cells[0].position, (1., -2., 0.5));
+        assert_eq!(read.cells[0].volume, 3.);
+        let meristem = read.meristem.unwrap();
+        assert_eq!(meristem.semi_axes, (4., 2., 4.));
+        assert_eq!(meristem.cell_count, 7);
+        assert!(matches!(
+            read.obstacles[..],
+            [Obstacle::Sphere { radius, .. }] if radius == 2.
+        ));
+    }
+
+    #[test]
+    fn cells_without_volume_are_not_read() {
+        let path = directory().join("no_volume.ron");
+        std::fs::write(&path, "(cells: [(position: (0., 0., 0.), volume: 0.)])").unwrap();
+        let read = Scenario::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+
+    #[test]
+    fn plane_obstacles_without_normal_are_not_read() {
+        let path = directory().join("no_normal.ron");
+        std::fs::write(
+            &path,
+            "(obstacles: [Plane(point: (0., 0., 0.), normal: (0., 0., 0.))])",
+        )
+        .unwrap();
+        let read = Scenario::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+
+    #[test]
+    fn the_default_scenario_is_valid() {
+        assert!(Scenario::default().validate().is_ok());
+    }
+
+    #[test]
+    fn the_requested_number_of_cells_is_placed() {
+        let positions = meristem((10., 5., 10.), 20, 1.).cell_positions().unwrap();
+        assert_eq!(positions.len(), 20);
+    }
+
+    #[test]
+    fn only_the_cells_that_fit_are_placed() {
+        // the step of the lattice is larger than the dome, only its center fits
+        let positions = meristem((0.5, 0.5, 0.5), 20, 1.).cell_positions().unwrap();
+        assert_eq!(positions, vec![Point3::new(1., 2., 3.)]);
+    }
+
+    #[test]
+    fn cells_are_packed_in_the_upper_half_of_the_dome() {
+        let meristem = meristem((6., 3., 4.), 1000, 1.);
+        let positions = meristem.cell_positions().unwrap();
+        let neighbour_distance = MERISTEM_CELL_SPACING * radius_from_volume(&1.);
+        for (i, position) in positions.iter().enumerate() {
+            let offset = position - Point3::from(meristem.center);
+            assert!(offset.y >= 0.);
+            assert!(
+                (offset.x / 6.).powi(2) + (offset.y / 3.).powi(2) + (offset.z / 4.).powi(2) <= 1.
+            );
+            for other in positions.iter().skip(i + 1) {
+                assert!(distance(position, other) >= neighbour_distance - 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn cells_without_volume_are_an_error() {
+        assert!(meristem((5., 5., 5.), 10, 0.).cell_positions().is_err());
+        assert!(meristem((5., 5., 5.), 10, -1.).cell_positions().is_err());
+        assert!(meristem((5., 5., 5.), 10, f32::NAN)
+            .cell_positions()
+            .is_err());
+    }
+
+    #[test]
+    fn a_flat_dome_is_an_error() {
+        assert!(meristem((5., 0., 5.), 10, 1.).cell_positions().is_err());
+        assert!(meristem((-5., 5., 5.), 10, 1.).cell_positions().is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::{error::Error, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::cell::GrowthLaw;
+
+/// Parameters of the simulation that can be tuned without recompiling.
+/// Read from a RON file, fields missing in the file keep their default value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationSettings {
+    /// curve the volume of a cell follows while it ages
+    pub growth_law: GrowthLaw,
+    /// volume at which a cell stops growing
+    pub size_threshold: f32,
+    /// steepness of the growth curve
+    pub growth_factor: f32,
+    /// relative amount by which the growth factors of a new cell may randomly differ, 0 disables it
+    pub growth_variation: f32,
+    /// how strongly overlapping neighbours slow down growth,
+    /// a cell stops growing when it shares this fraction of its volume, 0 disables it
+    pub contact_inhibition: f32,
+    /// how often per update overlapping cells are pushed apart
+    pub relaxation_iterations: u32,
+    /// number of updates two cells have to touch before they stick together
+    pub adhesion_updates: u32,
+    /// fraction of the gap between stuck together cells that is closed per update, 0 disables adhesion
+    pub adhesion_stiffness: f32,
+    /// ages at which cells enter the next maturation stage and how fast they grow in each
+    pub maturation: MaturationSettings,
+    /// sugar produced per update by a cell that is not shaded by its neighbours at noon
+    pub carbon_production_rate: f32,
+    /// sugar consumed per unit of volume a cell grows
+    pub carbon_growth_cost: f32,
+    /// sugar a new cell starts with
+    pub carbon_start_value: f32,
+    /// number of simulation updates a full day takes
+    pub day_length: u32,
+    /// volume of cells placed with the mouse
+    pub placed_cell_volume: f32,
+    /// detail of the sphere meshes of the cells
+    pub level_of_detail: u16,
+    /// milliseconds between two simulation updates
+    pub update_interval: u64,
+    /// distance between two lines of the reference grid on the ground
+    pub grid_spacing: f32,
+    /// number of grid lines in each direction from the origin
+    pub grid_lines: u32,
+}
+
+/// Ages are counted in updates since the cell was created,
+/// growth multipliers scale the growth of the cell while it is in the stage.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaturationSettings {
+    pub expanding_age: u32,
+    pub mature_age: u32,
+    pub senescent_age: u32,
+    pub young_growth: f32,
+    pub expanding_growth: f32,
+    pub mature_growth: f32,
+    pub senescent_growth: f32,
+}
+
+impl Default for MaturationSettings {
+    fn default() -> Self {
+        Self {
+            expanding_age: 50,
+            mature_age: 600,
+            senescent_age: 1500,
+            young_growth: 1.,
+            expanding_growth: 1.,
+            mature_growth: 1.,
+            senescent_growth: 0.,
+        }
+    }
+}
+
+/// Highest level of detail whose sphere mesh still fits into u16 indices.
+const MAX_LEVEL_OF_DETAIL: u16 = 180;
+/// Most grid lines in each direction, the grid is rebuilt every update.
+const MAX_GRID_LINES: u32 = 1000;
+
+impl SimulationSettings {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let settings: Self = ron::from_str(&content)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// returns an error describing the first value the simulation can not run with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.day_length == 0 {
+            return Err("day_length has to be at least 1".to_string());
+        }
+        if self.level_of_detail == 0 || self.level_of_detail > MAX_LEVEL_OF_DETAIL {
+            return Err(format!(
+                "level_of_detail has to be between 1 and {}, got {}",
+                MAX_LEVEL_OF_DETAIL, self.level_of_detail
+            ));
+        }
+        if !(0. ..1.).contains(&self.growth_variation) {
+            return Err(format!(
+                "growth_variation has to be at least 0 and below 1, got {}",
+                self.growth_variation
+            ));
+        }
+        if !self.size_threshold.is_finite() || self.size_threshold <= 0. {
+            return Err(format!(
+                "size_threshold has to be positive, got {}",
+                self.size_threshold
+            ));
+        }
+        if !self.growth_factor.is_finite() {
+            return Err(format!(
+                "growth_factor has to be a number, got {}",
+                self.growth_factor
+            ));
+        }
+        if !self.placed_cell_volume.is_finite() || self.placed_cell_volume <= 0. {
+            return Err(format!(
+                "placed_cell_volume has to be positive, got {}",
+                self.placed_cell_volume
+            ));
+        }
+        if !self.grid_spacing.is_finite() || self.grid_spacing <= 0. {
+            return Err(format!(
+                "grid_spacing has to be positive, got {}",
+                self.grid_spacing
+            ));
+        }
+        if self.grid_lines > MAX_GRID_LINES {
+            return Err(format!(
+                "grid_lines can be at most {}, got {}",
+                MAX_GRID_LINES, self.grid_lines
+            ));
+        }
+        if self.update_interval == 0 {
+            return Err("update_interval has to be at least 1".to_string());
+        }
+        for (name, value) in [
+            ("carbon_production_rate", self.carbon_production_rate),
+            ("carbon_growth_cost", self.carbon_growth_cost),
+            ("carbon_start_value", self.carbon_start_value),
+        ] {
+            if !value.is_finite() || value < 0. {
+                return Err(format!("{} cannot be negative, got {}", name, value));
+            }
+        }
+        let maturation = &self.maturation;
+        if maturation.expanding_age > maturation.mature_age
+            || maturation.mature_age > maturation.senescent_age
+        {
+            return Err(format!(
+                "The maturation ages have to be in order, got expanding {}, mature {} and senescent {}",
+                maturation.expanding_age, maturation.mature_age, maturation.senescent_age
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            growth_law: GrowthLaw::Logistic,
+            size_threshold: 20.,
+            growth_factor: 0.0005,
+            growth_variation: 0.,
+            contact_inhibition: 1.,
+            relaxation_iterations: 4,
+            maturation: MaturationSettings::default(),
+            adhesion_updates: 5,
+            adhesion_stiffness: 0.2,
+            carbon_production_rate: 0.01,
+            carbon_growth_cost: 1.,
+            carbon_start_value: 1.,
+            day_length: 300,
+            placed_cell_volume: 1.,
+            level_of_detail: 20,
+            update_interval: 200,
+            grid_spacing: 1.,
+            grid_lines: 10,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(change: impl Fn(&mut SimulationSettings)) -> bool {
+        let mut settings = SimulationSettings::default();
+        change(&mut settings);
+        settings.validate().is_err()
+    }
+
+    #[test]
+    fn the_defaults_are_valid() {
+        assert!(SimulationSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn an_update_interval_of_zero_is_invalid() {
+        assert!(invalid(|settings| settings.update_interval = 0));
+    }
+
+    #[test]
+    fn negative_carbon_values_are_invalid() {
+        assert!(invalid(|settings| settings.carbon_production_rate = -0.1));
+        assert!(invalid(|settings| settings.carbon_growth_cost = -1.));
+        assert!(invalid(|settings| settings.carbon_start_value = -1.));
+        assert!(invalid(|settings| settings.carbon_start_value = f32::NAN));
+        assert!(!invalid(|settings| settings.carbon_production_rate = 0.));
+    }
+
+    #[test]
+    fn maturation_ages_have_to_be_in_order() {
+        assert!(invalid(|settings| settings.maturation.expanding_age = 700));
+        assert!(invalid(|settings| settings.maturation.senescent_age = 100));
+        assert!(!invalid(|settings| {
+            settings.maturation.expanding_age = 10;
+            settings.maturation.mature_age = 10;
+            settings.maturation.senescent_age = 10;
+        }));
+    }
+
+    #[test]
+    fn too_many_grid_lines_are_invalid() {
+        assert!(invalid(|settings| settings.grid_lines = u32::MAX));
+        assert!(!invalid(|settings| settings.grid_lines = 0));
+    }
+
+    #[test]
+    fn values_checked_before_are_still_invalid() {
+        assert!(invalid(|settings| settings.day_length = 0));
+        assert!(invalid(|settings| settings.level_of_detail = 0));
+        assert!(invalid(|settings| settings.growth_variation = 1.));
+        assert!(invalid(|settings| settings.size_threshold = 0.));
+        assert!(invalid(|settings| settings.growth_factor = f32::INFINITY));
+        assert!(invalid(|settings| settings.placed_cell_volume = -1.));
+        assert!(invalid(|settings| settings.grid_spacing = 0.));
+    }
+}
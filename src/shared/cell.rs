@@ -1,20 +1,19 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    sync::{
-        mpsc::{channel, Sender},
-        Arc, Mutex, RwLock,
-    },
-    thread,
+    sync::{Arc, Mutex, RwLock},
 };
 
 use crate::{
-    engine::cell_renderer::{radius_from_volume, CellRenderer},
-    model::{cell::BiologicalCell, entity::Entity},
+    engine::cell_renderer::CellRenderer,
+    model::{cell::BiologicalCell, entity::Entity, settings::SimulationSettings},
 };
 use cgmath::{BaseFloat, Point3};
 
-use super::math::distance;
+use super::{
+    math::{distance, radius_from_volume},
+    rng::SimulationRng,
+};
 
 #[derive(Clone, Debug)]
 pub struct CellInformation<T: BaseFloat> {
@@ -58,11 +57,19 @@ pub struct Cell {
 }
 
 impl Cell {
-    pub fn new(position: Point3<f32>, volume: f32, events: Arc<EventSystem>) -> Self {
+    pub fn new(
+        position: Point3<f32>,
+        volume: f32,
+        events: Arc<EventSystem>,
+        settings: &SimulationSettings,
+        rng: &mut SimulationRng,
+    ) -> Self {
         let bio = Arc::new(RwLock::new(BiologicalCell::new(
             &position,
             volume,
             Arc::clone(&events),
+            settings,
+            rng,
         )));
         let renderer = Arc::new(RwLock::new(CellRenderer::new(
             &position,
@@ -83,8 +90,10 @@ pub fn near(pos1: &Point3<f32>, radius1: f32, pos2: &Point3<f32>, radius2: f32)
     dist < radius1 + radius2
 }
 
+type EventHandler = Arc<dyn Fn(Arc<CellEvent>) + Send + Sync>;
+
 pub struct EventSystem {
-    subscribers: Mutex<HashMap<u64, RwLock<Vec<Sender<Arc<CellEvent>>>>>>,
+    subscribers: Mutex<HashMap<u64, Vec<EventHandler>>>,
 }
 
 impl Debug for EventSystem {
@@ -108,43 +117,31 @@ impl EventSystem {
     /// The given id can be used to directly address all subscribers registered under this id.
     pub fn subscribe<F>(&self, id: u64, handler: F)
     where
-        F: Fn(Arc<CellEvent>) + Send + 'static,
+        F: Fn(Arc<CellEvent>) + Send + Sync + 'static,
     {
-        let (sender, receiver) = channel();
-        {
-            let mut subscribers = self.subscribers.lock().unwrap();
-            let previous = subscribers.get(&id);
-            if previous.is_none() {
-                subscribers.insert(id, RwLock::new(vec![sender]));
-            } else {
-                let mut previous = previous.unwrap().write().unwrap();
-                previous.push(sender);
-            }
-        }
-        thread::spawn(move || {
-            for event in receiver {
-                handler(event);
-            }
-        });
+        self.subscribers
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_default()
+            .push(Arc::new(handler));
+    }
+
+    /// removes all subscribers registered under the id, their handlers stop receiving events
+    pub fn unsubscribe(&self, id: u64) {
+        self.subscribers.lock().unwrap().remove(&id);
     }
 
-    /// notifies the cell specified by the id given in the event
+    /// notifies the cell specified by the id given in the event.
+    /// The handlers run before this returns, so the change can be read right after.
     pub fn notify(&self, event: Arc<CellEvent>) {
-        let subscribers = self.subscribers.lock().unwrap();
-        let sender = subscribers.get(&event.id);
-        match sender {
-            Option::Some(senders) => {
-                let senders = senders.read().unwrap();
-                senders.iter().for_each(|sender| {
-                    let success = sender.send(Arc::clone(&event));
-                    if success.is_err() {
-                        println!(
-                            "{:?} could not be sent! Error: {}",
-                            event,
-                            success.unwrap_err()
-                        );
-                    }
-                });
+        // handlers may notify again, so they are not called while the subscribers are locked
+        let handlers = self.subscribers.lock().unwrap().get(&event.id).cloned();
+        match handlers {
+            Some(handlers) => {
+                for handler in handlers {
+                    handler(Arc::clone(&event));
+                }
             }
             None => {
                 println!(
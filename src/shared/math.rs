@@ -1,6 +1,6 @@
 use std::{i16, iter::Sum};
 
-use cgmath::{BaseFloat, ElementWise, InnerSpace, Point3, Vector3};
+use cgmath::{BaseFloat, ElementWise, EuclideanSpace, InnerSpace, Point3, Vector3};
 
 #[derive(Clone, Debug)]
 pub struct Line<T> {
@@ -75,28 +75,186 @@ pub enum Point2PlaneClassification {
     Intersects,
 }
 
-pub fn line_plane_intersection<T: BaseFloat>(
-    line: &Line<T>,
-    plane: &Plane<T>,
-) -> Line2PlaneClassification<T> {
-    let line_2_plane = plane.pos - line.pos;
-    let denominator = plane.normal.dot(line.dir);
+/// returns how far along the line the sphere is entered first,
+/// measured in multiples of the direction and only in front of the line position
+pub fn ray_sphere_intersection<T: BaseFloat>(
+    ray: &Line<T>,
+    center: &Point3<T>,
+    radius: T,
+) -> Option<T> {
+    let two = T::one() + T::one();
+    let center_2_pos = ray.pos - center.to_vec();
+    let a = ray.dir.dot(ray.dir);
+    let b = two * ray.dir.dot(center_2_pos);
+    let c = center_2_pos.dot(center_2_pos) - radius * radius;
+    let discriminant = b * b - two * two * a * c;
+    if a == T::zero() || discriminant < T::zero() {
+        return None;
+    }
+    let near = (-b - discriminant.sqrt()) / (two * a);
+    let far = (-b + discriminant.sqrt()) / (two * a);
+    if near >= T::zero() {
+        Some(near)
+    } else if far >= T::zero() {
+        // the line position is inside the sphere
+        Some(T::zero())
+    } else {
+        None
+    }
+}
 
-    // If denominator is close to zero, the line is parallel to the plane
-    if denominator.abs() < T::epsilon() {
-        return Line2PlaneClassification::Parallel;
+/// returns the point between start and end that is closest to the point
+pub fn closest_point_on_segment<T: BaseFloat>(
+    point: &Point3<T>,
+    start: &Point3<T>,
+    end: &Point3<T>,
+) -> Point3<T> {
+    let segment = end - start;
+    let length_squared = segment.magnitude2();
+    if length_squared == T::zero() {
+        return *start;
     }
+    let t = (point - start).dot(segment) / length_squared;
+    start + segment * t.max(T::zero()).min(T::one())
+}
 
-    let t = plane.normal.dot(line_2_plane) / denominator;
+pub fn radius_from_volume(volume: &f32) -> f32 {
+    // r = ((3V)/(4PI))^(1/3)
+    f32::powf((3. * volume) / (4. * std::f32::consts::PI), 1. / 3.)
+}
 
-    let intersection = line.pos + (line.dir * t);
-    Line2PlaneClassification::Intersects(intersection)
+/// returns the volume two spheres with the given radii and distance between their centers share
+pub fn sphere_overlap_volume<T: BaseFloat>(radius1: T, radius2: T, distance: T) -> T {
+    let pi = T::from(std::f64::consts::PI).unwrap();
+    if distance >= radius1 + radius2 {
+        return T::zero();
+    }
+    if distance <= (radius1 - radius2).abs() {
+        // the smaller sphere lies completely inside the bigger one
+        let radius = radius1.min(radius2);
+        return T::from(4. / 3.).unwrap() * pi * radius.powi(3);
+    }
+    let (two, three, six, twelve) = (
+        T::from(2.).unwrap(),
+        T::from(3.).unwrap(),
+        T::from(6.).unwrap(),
+        T::from(12.).unwrap(),
+    );
+    pi * (radius1 + radius2 - distance).powi(2)
+        * (distance * distance + two * distance * (radius1 + radius2) + six * radius1 * radius2
+            - three * (radius1 * radius1 + radius2 * radius2))
+        / (twelve * distance)
 }
 
-#[derive(PartialEq, Eq)]
-pub enum Line2PlaneClassification<T: BaseFloat> {
-    /// line parallel to the plane
-    Parallel,
-    /// line intersects with the plane
-    Intersects(Vector3<T>),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn point(x: f32, y: f32, z: f32) -> Point3<f32> {
+        Point3 { x, y, z }
+    }
+
+    #[test]
+    fn distance_uses_all_axes() {
+        assert!((distance(&point(0., 0., 0.), &point(1., 2., 2.)) - 3.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn mean_of_points() {
+        let points = vec![point(0., 0., 0.), point(2., 4., -2.)];
+        assert_eq!(mean(&points), point(1., 2., -1.));
+    }
+
+    #[test]
+    fn ray_hits_sphere_in_front() {
+        let ray = Line {
+            pos: Vector3::new(-5., 0., 0.),
+            dir: Vector3::new(1., 0., 0.),
+        };
+        let hit = ray_sphere_intersection(&ray, &point(0., 0., 0.), 1.).unwrap();
+        assert!((hit - 4.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_misses_sphere_behind_or_beside() {
+        let behind = Line {
+            pos: Vector3::new(5., 0., 0.),
+            dir: Vector3::new(1., 0., 0.),
+        };
+        assert_eq!(
+            ray_sphere_intersection(&behind, &point(0., 0., 0.), 1.),
+            None
+        );
+        let beside = Line {
+            pos: Vector3::new(-5., 2., 0.),
+            dir: Vector3::new(1., 0., 0.),
+        };
+        assert_eq!(
+            ray_sphere_intersection(&beside, &point(0., 0., 0.), 1.),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_starting_inside_sphere_hits_immediately() {
+        let ray = Line {
+            pos: Vector3::new(0.5, 0., 0.),
+            dir: Vector3::new(0., 1., 0.),
+        };
+        assert_eq!(
+            ray_sphere_intersection(&ray, &point(0., 0., 0.), 1.),
+            Some(0.)
+        );
+    }
+
+    #[test]
+    fn closest_point_is_clamped_to_the_segment() {
+        let start = point(0., 0., 0.);
+        let end = point(2., 0., 0.);
+        assert_eq!(
+            closest_point_on_segment(&point(1., 3., 0.), &start, &end),
+            point(1., 0., 0.)
+        );
+        assert_eq!(
+            closest_point_on_segment(&point(-4., 1., 0.), &start, &end),
+            start
+        );
+        assert_eq!(
+            closest_point_on_segment(&point(9., 0., 1.), &start, &end),
+            end
+        );
+        assert_eq!(
+            closest_point_on_segment(&point(9., 0., 1.), &start, &start),
+            start
+        );
+    }
+
+    #[test]
+    fn radius_of_a_sphere_volume() {
+        let volume = 4. / 3. * std::f32::consts::PI * 2f32.powi(3);
+        assert!((radius_from_volume(&volume) - 2.).abs() < EPSILON);
+        assert_eq!(radius_from_volume(&0.), 0.);
+    }
+
+    #[test]
+    fn overlap_volume_of_separate_and_nested_spheres() {
+        assert_eq!(sphere_overlap_volume(1., 1., 2.), 0.);
+        assert_eq!(sphere_overlap_volume(1., 1., 3.), 0.);
+        let small_sphere = 4. / 3. * std::f32::consts::PI * 0.5f32.powi(3);
+        assert!((sphere_overlap_volume(2., 0.5, 1.) - small_sphere).abs() < EPSILON);
+        assert!((sphere_overlap_volume(0.5, 2., 0.) - small_sphere).abs() < EPSILON);
+    }
+
+    #[test]
+    fn overlap_volume_of_intersecting_spheres() {
+        // two unit spheres one radius apart share two caps of height 0.5
+        let cap = std::f32::consts::PI * 0.5f32.powi(2) * (3. - 0.5) / 3.;
+        assert!((sphere_overlap_volume(1., 1., 1.) - 2. * cap).abs() < EPSILON);
+        assert!(
+            (sphere_overlap_volume(1f32, 2., 2.) - sphere_overlap_volume(2., 1., 2.)).abs()
+                < EPSILON
+        );
+    }
 }
@@ -1,2 +1,3 @@
 pub mod cell;
 pub mod math;
+pub mod rng;